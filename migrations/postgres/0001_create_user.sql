@@ -4,4 +4,7 @@ CREATE TABLE IF NOT EXISTS "user" (
     "embed" boolean NOT NULL,
     "data" character varying(2000) NOT NULL,
     PRIMARY KEY ("discord_id")
-)
\ No newline at end of file
+);
+
+-- databases created by the old docker init script don't have the token yet
+ALTER TABLE "user" ADD COLUMN IF NOT EXISTS "token" uuid UNIQUE;
@@ -0,0 +1,5 @@
+ALTER TABLE "user" ALTER COLUMN "data" TYPE jsonb USING "data"::jsonb;
+
+-- e.g. all users of a class: WHERE "data"->>'class_name' = 'TIG21'
+CREATE INDEX IF NOT EXISTS "user_class_name_idx" ON "user" (("data"->>'class_name'));
+CREATE INDEX IF NOT EXISTS "user_data_idx" ON "user" USING gin ("data" jsonb_path_ops);
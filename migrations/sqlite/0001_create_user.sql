@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS "user" (
+    "discord_id" integer NOT NULL PRIMARY KEY,
+    "active" boolean NOT NULL,
+    "embed" boolean NOT NULL,
+    "data" text NOT NULL,
+    "token" blob UNIQUE
+);
+
+CREATE INDEX IF NOT EXISTS "user_class_name_idx" ON "user" (json_extract("data", '$.class_name'));
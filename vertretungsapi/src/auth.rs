@@ -0,0 +1,63 @@
+use crate::api_error::ApiError;
+use actix_web::{
+    http::{header, StatusCode},
+    HttpRequest,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scope {
+    /// read the plan of any user by its discord id
+    #[serde(rename = "plans.read")]
+    PlansRead,
+    /// create, change and delete the plan of any user
+    #[serde(rename = "plans.write")]
+    PlansWrite,
+    /// poll `/sources/{source}/update/{id}`
+    #[serde(rename = "updates")]
+    Updates,
+}
+
+impl Scope {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "plans.read" => Some(Scope::PlansRead),
+            "plans.write" => Some(Scope::PlansWrite),
+            "updates" => Some(Scope::Updates),
+            _ => None,
+        }
+    }
+}
+
+/// Keys of trusted services like the bot, each with the scopes it may use.
+#[derive(Debug, Default)]
+pub struct ApiKeys(BTreeMap<String, Vec<Scope>>);
+
+impl ApiKeys {
+    pub fn new(keys: BTreeMap<String, Vec<Scope>>) -> Self {
+        ApiKeys(keys)
+    }
+
+    /// The scopes of the bearer token, `None` without a known one.
+    pub fn scopes(&self, req: &HttpRequest) -> Option<&Vec<Scope>> {
+        let key = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))?;
+        self.0.get(key)
+    }
+
+    /// Checks the bearer token of the request for the given scope.
+    pub fn authorize(&self, req: &HttpRequest, scope: Scope) -> Result<(), ApiError> {
+        match self.scopes(req) {
+            Some(scopes) if scopes.contains(&scope) => Ok(()),
+            Some(_) => Err(ApiError::new(StatusCode::FORBIDDEN, "missing scope")),
+            None => Err(ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "missing or unknown api key",
+            )),
+        }
+    }
+}
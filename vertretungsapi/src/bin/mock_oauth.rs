@@ -0,0 +1,51 @@
+//! Minimal stand-in for the Discord OAuth2 endpoints to test the login locally.
+//! Run it and start the api with `DISCORD_OAUTH_URL=http://localhost:8090`.
+use actix_web::{http::header, web::Query, *};
+use serde::Deserialize;
+use std::env;
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    redirect_uri: String,
+    state: String,
+}
+
+#[get("/oauth2/authorize")]
+async fn authorize(query: Query<AuthorizeQuery>) -> impl Responder {
+    HttpResponse::Found()
+        .insert_header((
+            header::LOCATION,
+            format!(
+                "{}?code=mock-code&state={}",
+                query.redirect_uri, query.state
+            ),
+        ))
+        .finish()
+}
+
+#[post("/oauth2/token")]
+async fn token() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "access_token": "mock-token",
+        "token_type": "Bearer",
+        "scope": "identify",
+    }))
+}
+
+#[get("/users/@me")]
+async fn user() -> impl Responder {
+    let id = env::var("MOCK_DISCORD_ID").unwrap_or("1".to_string());
+    HttpResponse::Ok().json(serde_json::json!({ "id": id, "username": "mock" }))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(authorize).service(token).service(user);
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    HttpServer::new(|| App::new().configure(config))
+        .bind(("127.0.0.1", 8090))?
+        .run()
+        .await
+}
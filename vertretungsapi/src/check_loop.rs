@@ -1,34 +1,47 @@
+use crate::config::ScraperConfig;
 use crate::create_weeks_list::WeekZyklusList;
-use crate::vertretundsdings::vertretungsdings::{check_change, ChangeOption};
+use crate::vertretundsdings::revocations::RevocationLog;
+use crate::vertretundsdings::vertretungsdings::{check_change, link_moves, ChangeOption};
 use crate::UpdatedList;
 use crate::VdayCache;
 use chrono::Utc;
-use log::info;
+use log::{info, warn};
+use reqwest::{Client, Url};
+use scraper::{Html, Selector};
+use std::error::Error;
 use std::sync::Mutex;
-use std::time::Duration;
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use tokio::{task::JoinHandle, time::sleep};
 use tokio_util::sync::CancellationToken;
 
-pub fn init_vday_cache(
-    weeks: &Arc<Mutex<WeekZyklusList>>,
-) -> (
+/// The caches filled by a check loop, its task and the token stopping it.
+pub type CheckLoop = (
     Arc<VdayCache>,
     Arc<UpdatedList>,
+    Arc<Mutex<RevocationLog>>,
     JoinHandle<()>,
     CancellationToken,
-) {
+);
+
+pub fn init_vday_cache(weeks: &Arc<Mutex<WeekZyklusList>>, config: &ScraperConfig) -> CheckLoop {
     let cache = Arc::new(VdayCache::default());
     let updated_list = Arc::new(UpdatedList::default());
+    let revocations = Arc::new(Mutex::new(RevocationLog::default()));
     let cancel = CancellationToken::new();
     (
         Arc::clone(&cache),
         Arc::clone(&updated_list),
+        Arc::clone(&revocations),
         tokio::spawn(spawn_check_loop(
             Arc::clone(&cache),
             Arc::clone(&updated_list),
+            Arc::clone(&revocations),
             cancel.clone(),
-            Arc::clone(&weeks),
+            Arc::clone(weeks),
+            config.clone(),
         )),
         cancel,
     )
@@ -37,8 +50,10 @@ pub fn init_vday_cache(
 async fn spawn_check_loop(
     cache: Arc<VdayCache>,
     updated_list: Arc<UpdatedList>,
+    revocations: Arc<Mutex<RevocationLog>>,
     stop_signal: CancellationToken,
     week_zyklus_list: Arc<Mutex<WeekZyklusList>>,
+    config: ScraperConfig,
 ) {
     let mut times = HashMap::new();
     loop {
@@ -46,26 +61,75 @@ async fn spawn_check_loop(
         let mut vdays_local = Vec::new();
         let mut date = (Utc::now() - chrono::Duration::days(1)).naive_utc().date();
 
-        for i in 1..=10 {
+        // listed pages are all checked, probing ends at the first missing one
+        let (pages, listed) = match &config.index_url {
+            Some(index_url) => match listed_pages(index_url, &config).await {
+                Ok(pages) => (pages, true),
+                Err(err) => {
+                    warn!("reading the page index {index_url}: {err}");
+                    ((1..=config.pages).collect(), false)
+                }
+            },
+            None => ((1..=config.pages).collect(), false),
+        };
+        let mut seen = HashSet::new();
+        let mut failed = false;
+        let previous_times = times.clone();
+
+        for i in pages {
             let last = if let Some(s) = times.get_mut(&i) {
                 s
             } else {
                 times.insert(i, String::new());
                 times.get_mut(&i).unwrap()
             };
-            match check_change(i, last, &mut date, Arc::clone(&week_zyklus_list)).await {
+            match check_change(i, last, &mut date, Arc::clone(&week_zyklus_list), &config).await {
                 ChangeOption::Some(vday) => {
                     vdays_local.push(vday);
                     updated = true;
                 }
                 ChangeOption::Same(vday) => vdays_local.push(vday),
-                ChangeOption::None => continue,
+                ChangeOption::None => {}
+                ChangeOption::End if listed => continue,
                 ChangeOption::End => break,
+                ChangeOption::Failed => {
+                    failed = true;
+                    break;
+                }
             };
+            seen.insert(i);
         }
-        if let Ok(mut vdays) = cache.try_lock() {
-            vdays.clear();
-            vdays.append(&mut vdays_local);
+        // a page which could not be read is no withdrawn day, so the days
+        // of the last check stay until all pages can be read again
+        if failed {
+            warn!("reading the pages failed, keeping the last ones");
+            times = previous_times;
+            updated = false;
+        } else {
+            // pages that are gone take their day with them
+            let gone: Vec<i64> = times
+                .keys()
+                .filter(|page| !seen.contains(*page))
+                .copied()
+                .collect();
+            for page in gone {
+                if !times.remove(&page).unwrap_or_default().is_empty() {
+                    info!("page {page} is gone");
+                    updated = true;
+                }
+            }
+            link_moves(&mut vdays_local);
+            if let Ok(mut vdays) = cache.lock() {
+                if let Ok(mut revocations) = revocations.lock() {
+                    let today = Utc::now().date_naive();
+                    if revocations.record(&vdays, &vdays_local, today) {
+                        info!("substitutions revoked");
+                        updated = true;
+                    }
+                }
+                vdays.clear();
+                vdays.append(&mut vdays_local);
+            }
         }
         if updated {
             if let Ok(mut list) = updated_list.try_lock() {
@@ -76,7 +140,7 @@ async fn spawn_check_loop(
         info!("checked for updates");
 
         tokio::select! {
-            _ = sleep(Duration::from_secs(900)) => {
+            _ = sleep(config.interval()) => {
                 continue;
             }
 
@@ -87,3 +151,32 @@ async fn spawn_check_loop(
         };
     }
 }
+
+/// The numbers of the substitution pages linked from the index page, in
+/// order and at most `pages` of them. Untis indexes link them or show them
+/// in frames.
+async fn listed_pages(index_url: &str, config: &ScraperConfig) -> Result<Vec<i64>, Box<dyn Error>> {
+    let text = Client::new()
+        .get(index_url)
+        .basic_auth(&config.user, config.password.as_ref())
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let base = Url::parse(index_url)?;
+    let doc = Html::parse_document(&text);
+    let link_selector = Selector::parse("a[href], frame[src], iframe[src]")?;
+    let mut pages: Vec<i64> = doc
+        .select(&link_selector)
+        .filter_map(|link| {
+            let link = link.value();
+            let url = base.join(link.attr("href").or(link.attr("src"))?).ok()?;
+            config.page_of(url.as_str())
+        })
+        .collect();
+    pages.sort_unstable();
+    pages.dedup();
+    pages.truncate(config.pages as usize);
+    Ok(pages)
+}
@@ -0,0 +1,384 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, env, time::Duration};
+use vertretungscommon::config::load;
+use vertretungscommon::vertretung::grid::PeriodGrid;
+
+pub use vertretungscommon::config::{ConfigError, DatabaseConfig};
+
+use crate::auth::Scope;
+
+/// Variables starting with this prefix override single settings,
+/// e.g. `VERTRETUNGSAPI__SERVER__PORT=8080` sets `server.port`.
+const ENV_PREFIX: &str = "VERTRETUNGSAPI__";
+
+/// `{page}` in the url of the substitution pages, or `{page:03}` for the
+/// number padded with zeros to three digits.
+static PAGE_PLACEHOLDER: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{page(?::0(\d+))?\}").expect("page placeholder is valid"));
+
+/// Older variables which still override their setting.
+const ENV_ALIASES: [(&str, &str); 7] = [
+    ("DATABASE_URL", "database.url"),
+    ("SESSION_KEY", "server.session_key"),
+    ("STUNDENRASTER", "stundenraster"),
+    ("DISCORD_CLIENT_ID", "discord.client_id"),
+    ("DISCORD_CLIENT_SECRET", "discord.client_secret"),
+    ("DISCORD_REDIRECT_URI", "discord.redirect_uri"),
+    ("DISCORD_OAUTH_URL", "discord.base_url"),
+];
+
+/// Used when no source is configured, the pages of the BGy.
+pub const DEFAULT_SOURCE: &str = "bgy";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    /// keys of trusted services like the bot with the scopes they may use
+    pub api_keys: BTreeMap<String, Vec<Scope>>,
+    /// json file with the times of the periods for sources without their
+    /// own, the times of the BSZ without it
+    pub stundenraster: Option<String>,
+    /// the login with discord, disabled without it
+    pub discord: Option<OAuthConfig>,
+    /// the departments by their id, which is used in urls and plans
+    pub sources: BTreeMap<String, SourceConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// origins allowed to call the api from a browser
+    pub cors_origins: Vec<String>,
+    /// at least 64 bytes signing the session cookies, a random key without
+    /// it and sessions don't survive a restart
+    pub session_key: Option<String>,
+}
+
+/// Discord OAuth2 application, `base_url` can point to a local mock provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+    pub base_url: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourceConfig {
+    pub scraper: ScraperConfig,
+    pub blockplan: BlockplanConfig,
+    /// json file with the regular room occupancy for the free room finder
+    pub rooms: Option<String>,
+    /// lesson blocks of the day, plans have to fit into them
+    pub grid: PeriodGrid,
+    /// json file with the times of the periods of the grid
+    pub stundenraster: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScraperConfig {
+    /// url of the substitution pages, `{page}` is replaced by the page
+    /// number, `{page:03}` by the number padded to three digits
+    pub url: String,
+    /// page linking to the substitution pages, without it pages are probed
+    /// from 1 until one is missing
+    pub index_url: Option<String>,
+    pub user: String,
+    pub password: Option<String>,
+    /// the most pages checked
+    pub pages: i64,
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockplanConfig {
+    /// directory listing with the Blockplan pdfs
+    pub index_url: String,
+    /// the relative links of the listing are resolved against it
+    pub base_url: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8000,
+            cors_origins: Vec::new(),
+            session_key: None,
+        }
+    }
+}
+
+impl Default for OAuthConfig {
+    fn default() -> Self {
+        OAuthConfig {
+            client_id: String::new(),
+            client_secret: String::new(),
+            redirect_uri: String::new(),
+            base_url: "https://discord.com/api".to_string(),
+        }
+    }
+}
+
+impl Default for ScraperConfig {
+    fn default() -> Self {
+        ScraperConfig {
+            url: "https://geschuetzt.bszet.de/s-lk-vw/Vertretungsplaene/V_PlanBGy/V_DC_{page:03}.html"
+                .to_string(),
+            index_url: None,
+            user: "bsz-et-2223".to_string(),
+            password: None,
+            pages: 30,
+            interval_secs: 900,
+        }
+    }
+}
+
+impl Default for BlockplanConfig {
+    fn default() -> Self {
+        BlockplanConfig {
+            index_url: "https://frei.bszet.de/index.php?dir=/Blockplaene/BGy".to_string(),
+            base_url: "https://frei.bszet.de/".to_string(),
+        }
+    }
+}
+
+impl ScraperConfig {
+    pub fn page_url(&self, page: i64) -> String {
+        PAGE_PLACEHOLDER
+            .replace(&self.url, |caps: &Captures| {
+                let width = caps
+                    .get(1)
+                    .map_or(0, |width| width.as_str().parse().unwrap_or(0));
+                format!("{page:0width$}")
+            })
+            .into_owned()
+    }
+
+    /// The number of a page by its url, `None` for urls of other pages.
+    pub fn page_of(&self, url: &str) -> Option<i64> {
+        let placeholder = PAGE_PLACEHOLDER.find(&self.url)?;
+        let pattern = format!(
+            "(?i)^{}(\\d+){}$",
+            regex::escape(&self.url[..placeholder.start()]),
+            regex::escape(&self.url[placeholder.end()..]),
+        );
+        Regex::new(&pattern).ok()?.captures(url)?[1].parse().ok()
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+impl OAuthConfig {
+    /// Session cookies are only marked secure when the app is served over https.
+    pub fn secure_cookies(&self) -> bool {
+        self.redirect_uri.starts_with("https://")
+    }
+}
+
+impl Config {
+    /// Loads the defaults, then the toml file in `CONFIG_FILE` (`config.toml`
+    /// if it exists) and then the overrides from the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config: Config = load(ENV_PREFIX, &ENV_ALIASES)?;
+        config.load_list_vars()?;
+        if config.sources.is_empty() {
+            // `PW` predates the sources and only stands for the default one
+            let mut source = SourceConfig::default();
+            source.scraper.password = env::var("PW").ok();
+            config.sources.insert(DEFAULT_SOURCE.to_string(), source);
+        }
+        for source in config.sources.values_mut() {
+            if source.stundenraster.is_none() {
+                source.stundenraster = config.stundenraster.clone();
+            }
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// `API_KEYS` in the form `key:scope+scope,key:scope` and the comma
+    /// separated `CORS_ORIGINS` are older variables in their own format.
+    fn load_list_vars(&mut self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let api_keys = env::var("API_KEYS").unwrap_or_default();
+        for entry in api_keys.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (key, scopes) = match entry.split_once(':') {
+                Some(split) => split,
+                None => {
+                    errors.push(format!("API_KEYS entry without scopes: {entry}"));
+                    continue;
+                }
+            };
+            let scopes = scopes
+                .split('+')
+                .filter_map(|scope| {
+                    let parsed = Scope::from_str(scope);
+                    if parsed.is_none() {
+                        errors.push(format!("unknown scope in API_KEYS: {scope}"));
+                    }
+                    parsed
+                })
+                .collect();
+            self.api_keys.insert(key.to_string(), scopes);
+        }
+        let origins = env::var("CORS_ORIGINS").unwrap_or_default();
+        self.server.cors_origins.extend(
+            origins
+                .split(',')
+                .map(str::trim)
+                .filter(|origin| !origin.is_empty())
+                .map(str::to_string),
+        );
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError(errors)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        if self.server.port == 0 {
+            errors.push("server.port must not be 0".to_string());
+        }
+        if let Some(key) = &self.server.session_key {
+            if key.len() < 64 {
+                errors.push("server.session_key needs at least 64 bytes".to_string());
+            }
+        }
+        self.database.validate(&mut errors);
+        if self.api_keys.keys().any(|key| key.trim().is_empty()) {
+            errors.push("api_keys must not contain an empty key".to_string());
+        }
+        if let Some(discord) = &self.discord {
+            for (key, value) in [
+                ("client_id", &discord.client_id),
+                ("client_secret", &discord.client_secret),
+                ("redirect_uri", &discord.redirect_uri),
+            ] {
+                if value.is_empty() {
+                    errors.push(format!("discord.{key} is missing"));
+                }
+            }
+        }
+        for (id, source) in &self.sources {
+            source.validate(&format!("sources.{id}"), &mut errors);
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+            {
+                errors.push(format!(
+                    "sources.{id}: ids may only contain a-z, 0-9, - and _"
+                ));
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError(errors)),
+        }
+    }
+}
+
+impl SourceConfig {
+    fn validate(&self, path: &str, errors: &mut Vec<String>) {
+        let ScraperConfig {
+            url,
+            index_url,
+            password,
+            pages,
+            interval_secs,
+            ..
+        } = &self.scraper;
+        if let Err(err) = self.grid.validate() {
+            errors.push(format!("{path}.grid: {err}"));
+        }
+        match PAGE_PLACEHOLDER.find(url) {
+            None => errors.push(format!(
+                "{path}.scraper.url has to contain {{page}} or {{page:03}}"
+            )),
+            // 00{page} gives 0010 for page 10, a 0 of the name like in
+            // V10{page} is fine
+            Some(_) if url.contains("00{page}") => errors.push(format!(
+                "{path}.scraper.url has to pad with {{page:03}} instead of zeros"
+            )),
+            Some(_) => {}
+        }
+        if password.is_none() {
+            errors.push(format!("{path}.scraper.password is missing"));
+        }
+        if *pages < 1 {
+            errors.push(format!("{path}.scraper.pages must be at least 1"));
+        }
+        if *interval_secs == 0 {
+            errors.push(format!("{path}.scraper.interval_secs must be at least 1"));
+        }
+        for (key, url) in [
+            ("scraper.url", url),
+            ("blockplan.index_url", &self.blockplan.index_url),
+            ("blockplan.base_url", &self.blockplan.base_url),
+        ]
+        .into_iter()
+        .chain(index_url.iter().map(|url| ("scraper.index_url", url)))
+        {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(format!("{path}.{key} has to be a http(s) url"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScraperConfig, SourceConfig};
+
+    fn url_errors(url: &str) -> Vec<String> {
+        let source = SourceConfig {
+            scraper: ScraperConfig {
+                url: url.to_string(),
+                password: Some("pass".to_string()),
+                ..ScraperConfig::default()
+            },
+            ..SourceConfig::default()
+        };
+        let mut errors = Vec::new();
+        source.validate("sources.bgy", &mut errors);
+        errors
+    }
+
+    #[test]
+    fn only_zeros_in_front_of_the_page_are_rejected() {
+        assert!(url_errors("https://example.org/V_DC_{page:03}.html").is_empty());
+        assert!(url_errors("https://example.org/V10{page}.html").is_empty());
+        assert_eq!(url_errors("https://example.org/V00{page}.html").len(), 1);
+        assert_eq!(url_errors("https://example.org/V_DC.html").len(), 1);
+    }
+
+    #[test]
+    fn pages_and_their_urls() {
+        let config = ScraperConfig {
+            url: "https://example.org/V10{page}.html".to_string(),
+            ..ScraperConfig::default()
+        };
+        assert_eq!(config.page_url(7), "https://example.org/V107.html");
+        assert_eq!(config.page_of("https://example.org/V1012.html"), Some(12));
+
+        let config = ScraperConfig::default();
+        assert!(config.page_url(7).ends_with("/V_DC_007.html"));
+        assert_eq!(config.page_of(&config.page_url(12)), Some(12));
+    }
+}
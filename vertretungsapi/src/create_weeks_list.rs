@@ -4,27 +4,20 @@ use log::info;
 use lopdf::Document;
 use reqwest::Response;
 use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
 use std::{
     error::Error,
     fmt::Debug,
     sync::{Arc, Mutex},
 };
+use vertretungscommon::vertretung::vertretungsdings::Zyklus;
 
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
-pub enum Zyklus {
-    #[default]
-    I,
-    II,
-}
+use crate::config::BlockplanConfig;
 
-impl Zyklus {
-    fn from_str(s: &str) -> Option<Self> {
-        match s {
-            "I" => Some(Zyklus::I),
-            "II" => Some(Zyklus::II),
-            _ => None,
-        }
+fn parse_zyklus(s: &str) -> Option<Zyklus> {
+    match s {
+        "I" => Some(Zyklus::I),
+        "II" => Some(Zyklus::II),
+        _ => None,
     }
 }
 
@@ -42,7 +35,7 @@ impl<T: Clone> FirstAndLast<T> {
     }
 
     fn push(&mut self, value: T) {
-        if let None = self.first {
+        if self.first.is_none() {
             self.first = Some(value.clone());
         }
         self.last = Some(value);
@@ -68,13 +61,13 @@ impl ConvertedOption {
         if let Ok(date) = NaiveDate::parse_from_str(s, "%d.%m.%Y") {
             return ConvertedOption::Date(date);
         }
-        if let Some(wz) = Zyklus::from_str(s) {
+        if let Some(wz) = parse_zyklus(s) {
             return ConvertedOption::Zyklus(wz);
         }
         if s.parse::<i32>().is_ok() {
             return ConvertedOption::Reset;
         }
-        return ConvertedOption::None;
+        ConvertedOption::None
     }
 }
 
@@ -99,18 +92,19 @@ impl WeekZyklus {
         if date > &self.end {
             return Compared::Bigger;
         }
-        return Compared::Right(self.zyklus.clone());
+        Compared::Right(self.zyklus)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct WeekZyklusList(Vec<WeekZyklus>);
 
 impl WeekZyklusList {
-    pub async fn new() -> Result<Self, Box<dyn Error>> {
+    pub async fn new(config: &BlockplanConfig) -> Result<Self, Box<dyn Error>> {
         info!("new WeekZyklus");
-        let url = "https://frei.bszet.de/index.php?dir=/Blockplaene/BGy";
-        let buf = reqwest::get(url).and_then(Response::text).await?;
+        let buf = reqwest::get(&config.index_url)
+            .and_then(Response::text)
+            .await?;
         let doc = Html::parse_document(&buf);
         let item_selector = Selector::parse("td.FileListCellText")?;
         let a_selector = Selector::parse("a")?;
@@ -119,14 +113,14 @@ impl WeekZyklusList {
             for a in el.select(&a_selector) {
                 let url = a.value().attr("href").unwrap_or_default();
                 if url.ends_with(".pdf") {
-                    wzl.add(format!("https://frei.bszet.de/{url}").as_str())
+                    wzl.add(format!("{}{url}", config.base_url).as_str())
                         .await?;
                 }
             }
         }
         let x = (wzl.0.len() as f32).log2();
-        let needed_size = (2 as i32).pow(x.ceil() as u32) as usize;
-        let high_date = NaiveDate::from_ymd_opt(262143, 12, 31).unwrap();
+        let needed_size = 2_i32.pow(x.ceil() as u32) as usize;
+        let high_date = NaiveDate::MAX;
         while wzl.0.len() < needed_size {
             wzl.0.push(WeekZyklus {
                 start: high_date,
@@ -165,7 +159,7 @@ impl WeekZyklusList {
         let mut change = position;
         while change != 0 {
             change /= 2;
-            match self.0.get(position - 1)?.compare(&date) {
+            match self.0.get(position - 1)?.compare(date) {
                 Compared::Bigger => position += change,
                 Compared::Smaller => position -= change,
                 Compared::Right(z) => return Some(z),
@@ -175,6 +169,8 @@ impl WeekZyklusList {
     }
 }
 
-pub async fn create_weeks_list() -> Result<Arc<Mutex<WeekZyklusList>>, Box<dyn Error>> {
-    Ok(Arc::new(Mutex::new(WeekZyklusList::new().await?)))
+pub async fn create_weeks_list(
+    config: &BlockplanConfig,
+) -> Result<Arc<Mutex<WeekZyklusList>>, Box<dyn Error>> {
+    Ok(Arc::new(Mutex::new(WeekZyklusList::new(config).await?)))
 }
@@ -0,0 +1,135 @@
+use chrono::{NaiveDate, NaiveTime, Utc};
+use vertretungscommon::vertretung::vertretungsdings::{Day, Lesson};
+
+use crate::stundenraster::Stundenraster;
+
+const TIMEZONE: &str = "BEGIN:VTIMEZONE\r
+TZID:Europe/Berlin\r
+BEGIN:DAYLIGHT\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+TZNAME:CEST\r
+DTSTART:19700329T020000\r
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r
+END:DAYLIGHT\r
+BEGIN:STANDARD\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+TZNAME:CET\r
+DTSTART:19701025T030000\r
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r
+END:STANDARD\r
+END:VTIMEZONE\r
+";
+
+/// Renders the days of the plan `plan_id` as an iCalendar feed with one
+/// VEVENT per lesson.
+pub fn to_ical(days: &[Day], raster: &Stundenraster, plan_id: i64) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut cal = String::from(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//vertretungsdings//DE\r\nCALSCALE:GREGORIAN\r\n",
+    );
+    cal.push_str(TIMEZONE);
+
+    for day in days.iter().filter(|day| !day.holiday) {
+        let date = match day.date() {
+            Some(d) => d,
+            None => continue,
+        };
+        for slot in &day.slots {
+            // a lesson lasts from its period to the end of the block
+            for (n, lesson) in slot.lessons().into_iter().enumerate() {
+                let (start, end) = match (raster.get(lesson.time), raster.get(slot.periods.to)) {
+                    (Some(s), Some(e)) => (s.start, e.end),
+                    _ => {
+                        log::warn!("{date}: no time for the periods {}", slot.periods);
+                        continue;
+                    }
+                };
+                cal.push_str(&event(lesson, &date, start, end, n, plan_id, &stamp));
+            }
+        }
+    }
+
+    cal.push_str("END:VCALENDAR\r\n");
+    cal
+}
+
+fn event(
+    lesson: &Lesson,
+    date: &NaiveDate,
+    start: NaiveTime,
+    end: NaiveTime,
+    n: usize,
+    plan_id: i64,
+    stamp: &str,
+) -> String {
+    let mut summary = lesson.subject.to_string();
+    if !lesson.vtype.is_empty() {
+        summary = format!("{} ({})", summary, lesson.vtype);
+    }
+    let mut description = format!("Lehrer: {}", lesson.teacher);
+    if !lesson.vtype.is_empty() {
+        description.push_str(&format!("\nArt: {}", lesson.vtype));
+    }
+    if !lesson.message.is_empty() {
+        description.push_str(&format!("\nMitteilung: {}", lesson.message));
+    }
+
+    let lines = [
+        "BEGIN:VEVENT".to_string(),
+        // unique over all plans, calendars merge events of the same uid
+        format!(
+            "UID:{}-{}-{}-{}@vertretungsdings",
+            plan_id,
+            date.format("%Y%m%d"),
+            lesson.time,
+            n
+        ),
+        format!("DTSTAMP:{stamp}"),
+        format!(
+            "DTSTART;TZID=Europe/Berlin:{}",
+            date.and_time(start).format("%Y%m%dT%H%M%S")
+        ),
+        format!(
+            "DTEND;TZID=Europe/Berlin:{}",
+            date.and_time(end).format("%Y%m%dT%H%M%S")
+        ),
+        format!("SUMMARY:{}", escape(&summary)),
+        format!("LOCATION:{}", escape(&lesson.room)),
+        format!("DESCRIPTION:{}", escape(&description)),
+        format!(
+            "STATUS:{}",
+            if lesson.is_cancelled() {
+                "CANCELLED"
+            } else {
+                "CONFIRMED"
+            }
+        ),
+        "END:VEVENT".to_string(),
+    ];
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line after 75 octets as required by RFC 5545.
+fn fold(line: &str) -> String {
+    let mut res = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            res.push_str("\r\n ");
+            len = 1;
+        }
+        res.push(c);
+        len += c.len_utf8();
+    }
+    res.push_str("\r\n");
+    res
+}
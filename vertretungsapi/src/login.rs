@@ -0,0 +1,377 @@
+use actix_identity::Identity;
+use actix_session::Session;
+use actix_web::{
+    cookie::Key,
+    http::{header, StatusCode},
+    web::{self, Data, Json, Query},
+    *,
+};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use uuid::Uuid;
+use vertretungscommon::storage::Storage;
+
+use crate::api_error::ApiError;
+use crate::config::OAuthConfig;
+use crate::plans::{parse_plan, plan_by_id, upsert_plan};
+use crate::sources::Sources;
+use crate::{week_for_plan, WeekQuery};
+
+const STATE_KEY: &str = "oauth_state";
+
+/// Key signing the session cookies, a random one without a configured key.
+pub fn session_key(key: Option<&str>) -> Key {
+    match key {
+        Some(key) => Key::from(key.as_bytes()),
+        None => {
+            log::warn!("no server.session_key configured, sessions won't survive a restart");
+            Key::generate()
+        }
+    }
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(login)
+        .service(callback)
+        .service(logout)
+        .service(me)
+        .service(my_plan)
+        .service(put_my_plan)
+        .service(put_my_settings)
+        .service(my_week);
+}
+
+#[get("/login")]
+async fn login(session: Session, oauth: Data<OAuthConfig>) -> impl Responder {
+    let state = Uuid::new_v4().to_string();
+    if let Err(err) = session.insert(STATE_KEY, &state) {
+        return HttpResponse::InternalServerError().body(err.to_string());
+    }
+    let url = Url::parse_with_params(
+        &format!("{}/oauth2/authorize", oauth.base_url),
+        &[
+            ("response_type", "code"),
+            ("client_id", oauth.client_id.as_str()),
+            ("scope", "identify"),
+            ("state", state.as_str()),
+            ("redirect_uri", oauth.redirect_uri.as_str()),
+        ],
+    );
+    match url {
+        Ok(url) => HttpResponse::Found()
+            .insert_header((header::LOCATION, url.to_string()))
+            .finish(),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct CallbackQuery {
+    code: String,
+    state: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct DiscordUser {
+    id: String,
+}
+
+#[get("/auth/callback")]
+async fn callback(
+    req: HttpRequest,
+    query: Query<CallbackQuery>,
+    session: Session,
+    oauth: Data<OAuthConfig>,
+) -> impl Responder {
+    let expected = session.get::<String>(STATE_KEY).ok().flatten();
+    session.remove(STATE_KEY);
+    if expected.as_ref() != Some(&query.state) {
+        return HttpResponse::BadRequest().body("invalid oauth state");
+    }
+
+    let discord_id = match discord_id_by_code(&query.code, &oauth).await {
+        Ok(id) => id,
+        Err(err) => return HttpResponse::BadGateway().body(err.to_string()),
+    };
+    if let Err(err) = Identity::login(&req.extensions(), discord_id) {
+        return HttpResponse::InternalServerError().body(err.to_string());
+    }
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/me"))
+        .finish()
+}
+
+async fn discord_id_by_code(code: &str, oauth: &OAuthConfig) -> Result<String, Box<dyn Error>> {
+    let client = Client::new();
+    let token: TokenResponse = client
+        .post(format!("{}/oauth2/token", oauth.base_url))
+        .form(&[
+            ("client_id", oauth.client_id.as_str()),
+            ("client_secret", oauth.client_secret.as_str()),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", oauth.redirect_uri.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let user: DiscordUser = client
+        .get(format!("{}/users/@me", oauth.base_url))
+        .bearer_auth(token.access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(user.id)
+}
+
+#[get("/logout")]
+async fn logout(identity: Option<Identity>) -> impl Responder {
+    if let Some(identity) = identity {
+        identity.logout();
+    }
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/"))
+        .finish()
+}
+
+pub fn discord_id(identity: &Identity) -> Option<i64> {
+    identity.id().ok()?.parse().ok()
+}
+
+#[derive(Serialize)]
+struct Me {
+    discord_id: i64,
+    has_plan: bool,
+    active: bool,
+    embed: bool,
+    /// secret for `/ical/{token}.ics` and `/link/{token}/...`
+    token: Option<Uuid>,
+}
+
+#[get("/me")]
+async fn me(identity: Identity, storage: Data<dyn Storage>) -> Result<HttpResponse, ApiError> {
+    let id =
+        discord_id(&identity).ok_or(ApiError::new(StatusCode::UNAUTHORIZED, "not logged in"))?;
+    let me = match storage.user(id).await? {
+        Some(user) => Me {
+            discord_id: id,
+            has_plan: true,
+            active: user.active,
+            embed: user.embed,
+            token: match user.token {
+                Some(token) => Some(token),
+                None => storage.ensure_token(id).await?,
+            },
+        },
+        None => Me {
+            discord_id: id,
+            has_plan: false,
+            active: false,
+            embed: false,
+            token: None,
+        },
+    };
+    Ok(HttpResponse::Ok().json(me))
+}
+
+#[derive(Deserialize)]
+struct Settings {
+    active: Option<bool>,
+    embed: Option<bool>,
+}
+
+/// Turns the discord notifications on or off, like `!set` and `!embed`.
+#[put("/me/settings")]
+async fn put_my_settings(
+    identity: Identity,
+    settings: Json<Settings>,
+    storage: Data<dyn Storage>,
+) -> Result<HttpResponse, ApiError> {
+    let id =
+        discord_id(&identity).ok_or(ApiError::new(StatusCode::UNAUTHORIZED, "not logged in"))?;
+    let mut found = true;
+    if let Some(active) = settings.active {
+        found &= storage.set_active(id, active).await?;
+    }
+    if let Some(embed) = settings.embed {
+        found &= storage.set_embed(id, embed).await?;
+    }
+    match found {
+        true => Ok(HttpResponse::NoContent().finish()),
+        false => Err(ApiError::not_found("plan not found")),
+    }
+}
+
+#[get("/me/plan")]
+async fn my_plan(identity: Identity, storage: Data<dyn Storage>) -> impl Responder {
+    let id = match discord_id(&identity) {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().finish(),
+    };
+    match plan_by_id(&id, storage.as_ref()).await {
+        Ok(plan) => HttpResponse::Ok().json(plan),
+        Err(err) => HttpResponse::NotFound().body(err.to_string()),
+    }
+}
+
+#[put("/me/plan")]
+async fn put_my_plan(
+    identity: Identity,
+    body: Json<Value>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    let id =
+        discord_id(&identity).ok_or(ApiError::new(StatusCode::UNAUTHORIZED, "not logged in"))?;
+    let plan = parse_plan(body.into_inner(), &sources)?;
+    upsert_plan(&id, &plan, storage.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+#[get("/me/week")]
+async fn my_week(
+    identity: Identity,
+    query: Query<WeekQuery>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    let id = match discord_id(&identity) {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().finish(),
+    };
+    let plan = match plan_by_id(&id, storage.as_ref()).await {
+        Ok(plan) => plan,
+        Err(err) => return HttpResponse::NotFound().body(err.to_string()),
+    };
+    match week_for_plan(&plan, &query, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+#[path = "bin/mock_oauth.rs"]
+mod mock_oauth;
+
+#[cfg(test)]
+mod tests {
+    use actix_identity::IdentityMiddleware;
+    use actix_session::{storage::CookieSessionStore, SessionMiddleware};
+    use actix_web::{cookie::Cookie, dev::ServiceResponse, test, App, HttpServer};
+    use reqwest::redirect::Policy;
+    use std::sync::Arc;
+
+    use super::mock_oauth;
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use vertretungscommon::storage;
+
+    const REDIRECT_URI: &str = "http://localhost/auth/callback";
+
+    /// Starts the mock provider on a free port and returns its url.
+    fn start_mock_provider() -> String {
+        let server = HttpServer::new(|| App::new().configure(mock_oauth::config))
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let port = server.addrs()[0].port();
+        actix_web::rt::spawn(server.run());
+        format!("http://127.0.0.1:{port}")
+    }
+
+    fn cookies(res: &ServiceResponse) -> Vec<Cookie<'static>> {
+        res.response().cookies().map(|c| c.into_owned()).collect()
+    }
+
+    fn location(res: &ServiceResponse) -> String {
+        let location = res.headers().get(header::LOCATION).unwrap();
+        location.to_str().unwrap().to_string()
+    }
+
+    #[actix_web::test]
+    async fn login_with_mock_provider() {
+        let oauth = OAuthConfig {
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_uri: REDIRECT_URI.to_string(),
+            base_url: start_mock_provider(),
+        };
+        let storage = storage::connect(&DatabaseConfig {
+            url: Some("sqlite::memory:".to_string()),
+            max_connections: 1,
+            ..DatabaseConfig::default()
+        })
+        .await
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(oauth))
+                .app_data(Data::from(Arc::clone(&storage)))
+                .wrap(IdentityMiddleware::default())
+                .wrap(
+                    SessionMiddleware::builder(CookieSessionStore::default(), Key::generate())
+                        .cookie_secure(false)
+                        .build(),
+                )
+                .configure(config),
+        )
+        .await;
+
+        let res =
+            test::call_service(&app, test::TestRequest::get().uri("/login").to_request()).await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let session = cookies(&res);
+
+        // the provider sends the browser back with a code and the state
+        let authorized = Client::builder()
+            .redirect(Policy::none())
+            .build()
+            .unwrap()
+            .get(location(&res))
+            .send()
+            .await
+            .unwrap();
+        let redirect = authorized.headers()["location"].to_str().unwrap();
+        let redirect = redirect.strip_prefix("http://localhost").unwrap();
+
+        let mut req = test::TestRequest::get().uri(redirect);
+        for cookie in &session {
+            req = req.cookie(cookie.clone());
+        }
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(location(&res), "/me");
+        let logged_in = cookies(&res);
+
+        let mut req = test::TestRequest::get().uri("/me");
+        for cookie in &logged_in {
+            req = req.cookie(cookie.clone());
+        }
+        let user: Value = test::call_and_read_body_json(&app, req.to_request()).await;
+        assert_eq!(user["discord_id"], 1);
+        assert_eq!(user["has_plan"], false);
+
+        let res = test::call_service(&app, test::TestRequest::get().uri("/me").to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        // the state was used up by the login
+        let forged = redirect.replace("state=", "state=forged");
+        let mut req = test::TestRequest::get().uri(&forged);
+        for cookie in &logged_in {
+            req = req.cookie(cookie.clone());
+        }
+        let res = test::call_service(&app, req.to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,43 +1,87 @@
+mod api_error;
+mod auth;
 mod check_loop;
+mod config;
 mod create_weeks_list;
+mod ical;
+mod login;
+mod plans;
+mod rooms;
+mod sources;
+mod stundenraster;
+mod substitutions;
 mod vertretundsdings;
 
 use actix_cors::Cors;
+use actix_identity::IdentityMiddleware;
+use actix_session::{storage::CookieSessionStore, SessionMiddleware};
 use actix_web::web::Data;
 use actix_web::{
-    web::{Json, Path},
+    web::{Json, Path, Query},
     *,
 };
 
-use chrono::NaiveDate;
-use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions};
-use sqlx::Row;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use uuid::Uuid;
+use vertretungscommon::migration::plan_from_value;
+use vertretungscommon::storage::{self, Storage};
+use vertretungscommon::vertretung::notes::DayNotes;
+use vertretungscommon::vertretung::revocations::Revocation;
+use vertretungscommon::vertretung::vertretungsdings::{get_day, Day, Plan, VDay};
 
 use std::error::Error;
 use std::sync::{Arc, Mutex};
 
-use check_loop::init_vday_cache;
-use create_weeks_list::{create_weeks_list, WeekZyklusList, Zyklus};
-use vertretundsdings::vertretungsdings::{get_day, Day, Plan, VDay};
+use api_error::ApiError;
+use auth::{ApiKeys, Scope};
+use config::{Config, ServerConfig};
+use ical::to_ical;
+use login::session_key;
+use plans::{plan_by_id, plan_by_token, upgrade_stored_plans};
+use sources::Sources;
+use vertretundsdings::vertretungsdings::get_week;
 
 pub type VdayCache = Mutex<Vec<VDay>>;
 pub type UpdatedList = Mutex<Vec<Uuid>>;
 
-#[get("/update/{id}")]
-async fn updated(id: Path<Uuid>, update_list: Data<UpdatedList>) -> impl Responder {
+#[get("/sources")]
+async fn get_sources(sources: Data<Sources>) -> impl Responder {
+    HttpResponse::Ok().json(sources.ids())
+}
+
+#[get("/sources/{source}/update/{id}")]
+async fn updated(
+    req: HttpRequest,
+    path: Path<(String, Uuid)>,
+    sources: Data<Sources>,
+    api_keys: Data<ApiKeys>,
+) -> impl Responder {
+    if let Err(err) = api_keys.authorize(&req, Scope::Updates) {
+        return err.error_response();
+    }
+    let (source, id) = path.into_inner();
+    let update_list = match sources.get(&source) {
+        Some(source) => &source.updated_list,
+        None => return HttpResponse::NotFound().json("unknown source"),
+    };
     let mut val = true;
     if let Ok(mut list) = update_list.try_lock() {
         match list.contains(&id) {
             true => val = false,
-            false => list.push(*id),
+            false => list.push(id),
         }
     }
     HttpResponse::Ok().json(val)
 }
 
-#[get("/vdays")]
-async fn get_vdays(vdays: Data<VdayCache>) -> impl Responder {
+#[get("/sources/{source}/vdays")]
+async fn get_vdays(source: Path<String>, sources: Data<Sources>) -> impl Responder {
+    let vdays = match sources.get(&source) {
+        Some(source) => &source.vdays,
+        None => return HttpResponse::NotFound().json("unknown source"),
+    };
     match vdays.try_lock() {
         Ok(data) => {
             let days: &Vec<VDay> = data.as_ref();
@@ -47,60 +91,252 @@ async fn get_vdays(vdays: Data<VdayCache>) -> impl Responder {
     }
 }
 
-#[post("/days")]
-async fn get_days(plan: Json<Plan>, vdays_data: Data<VdayCache>) -> impl Responder {
-    match vdays_data.try_lock() {
-        Ok(vdays) => {
-            let days: Vec<Day> = vdays.iter().map(|v| get_day(v, &plan)).flatten().collect();
-            HttpResponse::Ok().json(days)
+/// Lesson blocks of the source, which plans and days are split into.
+#[get("/sources/{source}/grid")]
+async fn get_grid(source: Path<String>, sources: Data<Sources>) -> impl Responder {
+    match sources.get(&source) {
+        Some(source) => HttpResponse::Ok().json(&source.grid),
+        None => HttpResponse::NotFound().json("unknown source"),
+    }
+}
+
+#[derive(Deserialize)]
+struct RevocationQuery {
+    since: Option<u64>,
+}
+
+/// Substitutions and days taken back, after the revocation `since` if given.
+/// Only those of days which are not over yet are kept.
+#[get("/sources/{source}/revocations")]
+async fn get_revocations(
+    source: Path<String>,
+    query: Query<RevocationQuery>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    let revocations = match sources.get(&source) {
+        Some(source) => &source.revocations,
+        None => return HttpResponse::NotFound().json("unknown source"),
+    };
+    match revocations.try_lock() {
+        Ok(log) => HttpResponse::Ok().json(log.since(query.since)),
+        _ => HttpResponse::InternalServerError().json(Vec::<Revocation>::new()),
+    }
+}
+
+#[derive(Serialize)]
+struct NotesOfDay<'a> {
+    day: &'a str,
+    #[serde(flatten)]
+    notes: &'a DayNotes,
+}
+
+/// The notes of the published days of the source, days without any are
+/// left out.
+#[get("/sources/{source}/notes")]
+async fn get_notes(source: Path<String>, sources: Data<Sources>) -> impl Responder {
+    let vdays = match sources.get(&source) {
+        Some(source) => &source.vdays,
+        None => return HttpResponse::NotFound().json("unknown source"),
+    };
+    match vdays.try_lock() {
+        Ok(data) => {
+            let notes: Vec<NotesOfDay> = data
+                .iter()
+                .filter(|vday| !vday.notes().is_empty())
+                .map(|vday| NotesOfDay {
+                    day: vday.day(),
+                    notes: vday.notes(),
+                })
+                .collect();
+            HttpResponse::Ok().json(notes)
         }
-        _ => HttpResponse::InternalServerError().json(Vec::<Day>::new()),
+        _ => HttpResponse::InternalServerError().json(Vec::<DayNotes>::new()),
+    }
+}
+
+#[post("/days")]
+async fn get_days(body: Json<Value>, sources: Data<Sources>) -> impl Responder {
+    let plan = match plan_from_value(body.into_inner()) {
+        Ok(plan) => plan,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    match days_for_plan(&plan, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
     }
 }
 
 #[get("/days/{plan_id}")]
 async fn get_days_by_plan_id(
+    req: HttpRequest,
     plan_id: Path<i64>,
-    dbconnection: Data<PgPool>,
-    vdays_data: Data<VdayCache>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+    api_keys: Data<ApiKeys>,
 ) -> impl Responder {
-    match days_by_plan_id(plan_id.as_ref(), dbconnection, vdays_data).await {
+    if let Err(err) = api_keys.authorize(&req, Scope::PlansRead) {
+        return err.error_response();
+    }
+    let plan = match plan_by_id(plan_id.as_ref(), storage.as_ref()).await {
+        Ok(plan) => plan,
+        Err(err) => return err.error_response(),
+    };
+    match days_for_plan(&plan, &sources) {
         Ok(days) => HttpResponse::Ok().json(days),
         Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
     }
 }
 
-async fn days_by_plan_id(
-    plan_id: &i64,
-    dbconnection: Data<PgPool>,
-    vdays_data: Data<VdayCache>,
-) -> Result<Vec<Day>, Box<dyn Error>> {
-    let row = sqlx::query("SELECT \"data\" FROM \"user\" WHERE \"discord_id\" = $1")
-        .bind(plan_id)
-        .fetch_one(dbconnection.as_ref())
-        .await?;
-    let str_data_plan = row.try_get(0)?;
-    let plan: Plan = serde_json::from_str(str_data_plan)?;
-    let vdays_res = vdays_data.try_lock().map_err(|err| err.to_string())?;
-    let vdays_vec: &Vec<VDay> = &vdays_res.as_ref();
-    let days: Vec<Day> = vdays_vec
+#[get("/link/{token}/days")]
+async fn get_days_by_token(
+    token: Path<Uuid>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    let plan = match plan_by_token(&token, storage.as_ref()).await {
+        Ok(plan) => plan,
+        Err(err) => return err.error_response(),
+    };
+    match days_for_plan(&plan, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+fn days_for_plan(plan: &Plan, sources: &Sources) -> Result<Vec<Day>, Box<dyn Error>> {
+    let source = sources.of_plan(plan)?;
+    let vdays_res = source.vdays.try_lock().map_err(|err| err.to_string())?;
+    let days: Vec<Day> = vdays_res
         .iter()
-        .map(|vday| get_day(vday, &plan))
-        .flatten()
+        .filter_map(|vday| get_day(vday, plan, &source.grid))
         .collect();
     Ok(days)
 }
 
-#[get("/zyklus/{date_str}")]
+#[derive(Deserialize)]
+struct WeekQuery {
+    date: Option<NaiveDate>,
+}
+
+#[post("/week")]
+async fn post_week(
+    body: Json<Value>,
+    query: Query<WeekQuery>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    let plan = match plan_from_value(body.into_inner()) {
+        Ok(plan) => plan,
+        Err(err) => return HttpResponse::BadRequest().body(err.to_string()),
+    };
+    match week_for_plan(&plan, &query, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::BadRequest().body(err.to_string()),
+    }
+}
+
+#[get("/week/{plan_id}")]
+async fn get_week_by_plan_id(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    query: Query<WeekQuery>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+    api_keys: Data<ApiKeys>,
+) -> impl Responder {
+    if let Err(err) = api_keys.authorize(&req, Scope::PlansRead) {
+        return err.error_response();
+    }
+    let plan = match plan_by_id(plan_id.as_ref(), storage.as_ref()).await {
+        Ok(plan) => plan,
+        Err(err) => return err.error_response(),
+    };
+    match week_for_plan(&plan, &query, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+#[get("/link/{token}/week")]
+async fn get_week_by_token(
+    token: Path<Uuid>,
+    query: Query<WeekQuery>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    let plan = match plan_by_token(&token, storage.as_ref()).await {
+        Ok(plan) => plan,
+        Err(err) => return err.error_response(),
+    };
+    match week_for_plan(&plan, &query, &sources) {
+        Ok(days) => HttpResponse::Ok().json(days),
+        Err(err) => HttpResponse::InternalServerError().body(err.to_string()),
+    }
+}
+
+fn week_for_plan(
+    plan: &Plan,
+    query: &WeekQuery,
+    sources: &Sources,
+) -> Result<Vec<Day>, Box<dyn Error>> {
+    let date = query.date.unwrap_or_else(|| Utc::now().naive_utc().date());
+    let source = sources.of_plan(plan)?;
+    let vdays = source.vdays.try_lock().map_err(|err| err.to_string())?;
+    let weeks = source.weeks.try_lock().map_err(|err| err.to_string())?;
+    Ok(get_week(&date, plan, &vdays, &weeks, &source.grid))
+}
+
+const ICAL_WEEKS: i64 = 3;
+
+#[get("/ical/{token}.ics")]
+async fn get_ical(
+    token: Path<Uuid>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> impl Responder {
+    match ical_by_token(&token, storage, &sources).await {
+        Ok(cal) => HttpResponse::Ok()
+            .content_type("text/calendar; charset=utf-8")
+            .body(cal),
+        Err(err) => err.error_response(),
+    }
+}
+
+/// The feed of the plan of `token`, `ApiError::not_found` if there is none.
+async fn ical_by_token(
+    token: &Uuid,
+    storage: Data<dyn Storage>,
+    sources: &Sources,
+) -> Result<String, ApiError> {
+    let user = storage
+        .user_by_token(token)
+        .await?
+        .ok_or(ApiError::not_found("plan not found"))?;
+    let plan = plan_from_value(user.data).map_err(ApiError::internal)?;
+
+    let today = Utc::now().naive_utc().date();
+    let source = sources.of_plan(&plan).map_err(ApiError::internal)?;
+    let vdays = source.vdays.try_lock().map_err(ApiError::internal)?;
+    let weeks = source.weeks.try_lock().map_err(ApiError::internal)?;
+    let days: Vec<Day> = (0..ICAL_WEEKS)
+        .flat_map(|week| {
+            let date = today + chrono::Duration::weeks(week);
+            get_week(&date, &plan, &vdays, &weeks, &source.grid)
+        })
+        .collect();
+    Ok(to_ical(&days, &source.raster, user.discord_id))
+}
+
+#[get("/sources/{source}/zyklus/{date_str}")]
 async fn get_week_zyklus_by_date(
-    date: Path<NaiveDate>,
-    week_zyklus_list: Data<Mutex<WeekZyklusList>>,
+    path: Path<(String, NaiveDate)>,
+    sources: Data<Sources>,
 ) -> impl Responder {
-    match week_zyklus_list
-        .try_lock()
-        .ok()
-        .and_then(|zl| zl.get(&date))
-    {
+    let (source, date) = path.into_inner();
+    let source = match sources.get(&source) {
+        Some(source) => source,
+        None => return HttpResponse::NotFound().json("unknown source"),
+    };
+    match source.weeks.try_lock().ok().and_then(|zl| zl.get(&date)) {
         Some(z) => HttpResponse::Ok().json(z),
         None => HttpResponse::InternalServerError().json(":|"),
     }
@@ -111,45 +347,91 @@ async fn main() -> Result<(), Box<dyn Error>> {
     dotenv::dotenv().ok();
 
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    log::info!("starting HTTP server at http://localhost:8000");
 
-    let week_list = create_weeks_list().await.expect("Err loading zyklus");
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            log::error!("{err}");
+            std::process::exit(1);
+        }
+    };
+    let ServerConfig {
+        host,
+        port,
+        cors_origins,
+        session_key: key,
+    } = config.server.clone();
+    log::info!("starting HTTP server at http://{host}:{port}");
 
-    let (vdays, updated_list, handle, cancel_token) = init_vday_cache(&week_list);
+    let (sources, check_loops) = Sources::init(&config.sources).await?;
+    let sources = Arc::new(sources);
 
-    let pg_pool = Arc::new(
-        PgPoolOptions::new()
-            .max_connections(15)
-            .connect_with(
-                PgConnectOptions::new()
-                    .host("db")
-                    .database("vertretungsdings")
-                    .username("postgres")
-                    .password("pass"),
-            )
-            .await
-            .expect("Err creating client"),
-    );
+    let api_keys = Arc::new(ApiKeys::new(config.api_keys.clone()));
+    let oauth = config.discord.clone().map(Arc::new);
+    let secure_cookies = oauth.as_ref().is_none_or(|o| o.secure_cookies());
+    let key = session_key(key.as_deref());
+
+    let storage = storage::connect(&config.database)
+        .await
+        .map_err(|err| format!("connecting to the database: {err}"))?;
+
+    tokio::spawn(upgrade_stored_plans(Arc::clone(&storage)));
 
     HttpServer::new(move || {
         App::new()
-            .app_data(Data::from(Arc::clone(&vdays)))
-            .app_data(Data::from(Arc::clone(&updated_list)))
-            .app_data(Data::from(Arc::clone(&pg_pool)))
-            .app_data(Data::from(Arc::clone(&week_list)))
-            .wrap(Cors::default().allow_any_origin().allow_any_method())
+            .app_data(Data::from(Arc::clone(&sources)))
+            .app_data(Data::from(Arc::clone(&storage)))
+            .app_data(Data::from(Arc::clone(&api_keys)))
+            .wrap(
+                cors_origins
+                    .iter()
+                    .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+                    .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
+                    .allowed_headers(vec![
+                        http::header::AUTHORIZATION,
+                        http::header::CONTENT_TYPE,
+                    ])
+                    // the web app sends the session cookie along
+                    .supports_credentials(),
+            )
+            .wrap(IdentityMiddleware::default())
+            .wrap(
+                SessionMiddleware::builder(CookieSessionStore::default(), key.clone())
+                    .cookie_secure(secure_cookies)
+                    .build(),
+            )
             .wrap(middleware::Logger::default())
+            .service(get_sources)
             .service(get_vdays)
+            .service(get_grid)
+            .service(get_notes)
+            .service(get_revocations)
             .service(updated)
             .service(get_days)
             .service(get_days_by_plan_id)
             .service(get_week_zyklus_by_date)
+            .service(post_week)
+            .service(get_week_by_plan_id)
+            .service(get_ical)
+            .service(get_days_by_token)
+            .service(get_week_by_token)
+            .configure(plans::config)
+            .configure(substitutions::config)
+            .configure(rooms::config)
+            .configure(|cfg| {
+                if let Some(oauth) = &oauth {
+                    cfg.app_data(Data::from(Arc::clone(oauth)));
+                    login::config(cfg);
+                }
+            })
     })
-    .bind(("0.0.0.0", 8000))?
+    .bind((host.as_str(), port))?
     .run()
     .await?;
-    cancel_token.cancel();
-    handle.await?;
+    for (handle, cancel_token) in check_loops {
+        cancel_token.cancel();
+        handle.await?;
+    }
     log::info!("application successfully shut down gracefully");
     Ok(())
 }
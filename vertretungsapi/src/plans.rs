@@ -0,0 +1,281 @@
+use actix_identity::Identity;
+use actix_web::{
+    http::StatusCode,
+    web::{self, Data, Json, Path},
+    *,
+};
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+use vertretungscommon::migration::{
+    migrate_plan_value, plan_from_value, plan_version, CURRENT_VERSION,
+};
+use vertretungscommon::storage::{Storage, User};
+use vertretungscommon::vertretung::vertretungsdings::Plan;
+
+use crate::api_error::ApiError;
+use crate::auth::{ApiKeys, Scope};
+use crate::login::discord_id;
+use crate::sources::Sources;
+use crate::vertretundsdings::validation::{
+    validate_grid, validate_plan_value, PlanError, PLAN_SCHEMA,
+};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    // registered before `/plans/{plan_id}` which would match them too
+    cfg.service(plan_schema)
+        .service(validate_plan)
+        .service(get_plan)
+        .service(create_plan)
+        .service(put_plan)
+        .service(patch_plan)
+        .service(delete_plan);
+}
+
+pub async fn plan_by_id(plan_id: &i64, storage: &dyn Storage) -> Result<Plan, ApiError> {
+    let user = storage
+        .user(*plan_id)
+        .await?
+        .ok_or(ApiError::not_found("plan not found"))?;
+    plan_from_value(user.data).map_err(ApiError::internal)
+}
+
+pub async fn plan_by_token(token: &Uuid, storage: &dyn Storage) -> Result<Plan, ApiError> {
+    let user = storage
+        .user_by_token(token)
+        .await?
+        .ok_or(ApiError::not_found("plan not found"))?;
+    plan_from_value(user.data).map_err(ApiError::internal)
+}
+
+/// Stores the plan, a new user starts active and without embeds.
+pub async fn upsert_plan(
+    plan_id: &i64,
+    plan: &Plan,
+    storage: &dyn Storage,
+) -> Result<(), ApiError> {
+    let value = serde_json::to_value(plan).map_err(ApiError::internal)?;
+    Ok(storage.upsert_plan(*plan_id, &value).await?)
+}
+
+/// Rewrites all stored plans of older versions in the current format.
+pub async fn upgrade_stored_plans(storage: Arc<dyn Storage>) {
+    let users = match storage.users(false).await {
+        Ok(users) => users,
+        Err(err) => {
+            log::error!("couldn't load plans to upgrade: {err}");
+            return;
+        }
+    };
+    let mut upgraded = 0;
+    for User {
+        discord_id, data, ..
+    } in users
+    {
+        if plan_version(&data) == CURRENT_VERSION {
+            continue;
+        }
+        let plan = match migrate_plan_value(data)
+            .and_then(|value| serde_json::from_value::<Plan>(value).map_err(|e| e.to_string()))
+        {
+            Ok(plan) => plan,
+            Err(err) => {
+                log::warn!("plan of {discord_id} can't be upgraded: {err}");
+                continue;
+            }
+        };
+        match upsert_plan(&discord_id, &plan, storage.as_ref()).await {
+            Ok(_) => upgraded += 1,
+            Err(err) => log::warn!("couldn't store upgraded plan of {discord_id}: {err}"),
+        }
+    }
+    log::info!("upgraded {upgraded} stored plans to version {CURRENT_VERSION}");
+}
+
+/// Validates a plan and checks that its source is configured.
+fn validate_with_sources(value: &Value, sources: &Sources) -> Result<Plan, Vec<PlanError>> {
+    let plan = validate_plan_value(value)?;
+    match sources.get(&plan.source) {
+        Some(source) => validate_grid(&plan, &source.grid).map(|_| plan),
+        None => Err(vec![PlanError::new(
+            "source",
+            format!(
+                "unknown source {:?}, expected one of {}",
+                plan.source,
+                sources.ids().iter().join(", ")
+            ),
+        )]),
+    }
+}
+
+/// Deserializes and validates a plan from a request body.
+pub fn parse_plan(value: Value, sources: &Sources) -> Result<Plan, ApiError> {
+    validate_with_sources(&value, sources).map_err(|errors| {
+        ApiError::bad_request("invalid plan")
+            .with_details(errors.iter().map(PlanError::to_string).collect())
+    })
+}
+
+#[get("/plans/schema")]
+async fn plan_schema() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("application/schema+json")
+        .body(PLAN_SCHEMA)
+}
+
+#[derive(Serialize)]
+struct ValidationResult {
+    valid: bool,
+    errors: Vec<PlanError>,
+}
+
+/// Reports all problems of a plan without storing it.
+#[post("/plans/validate")]
+async fn validate_plan(body: Json<Value>, sources: Data<Sources>) -> impl Responder {
+    let errors = match validate_with_sources(&body, &sources) {
+        Ok(_) => Vec::new(),
+        Err(errors) => errors,
+    };
+    HttpResponse::Ok().json(ValidationResult {
+        valid: errors.is_empty(),
+        errors,
+    })
+}
+
+/// A plan can be managed by its logged in owner or by a service with the scope.
+/// Someone known but not allowed gets 403, without valid credentials it's 401.
+fn authorize_plan(
+    req: &HttpRequest,
+    identity: &Option<Identity>,
+    api_keys: &ApiKeys,
+    plan_id: i64,
+    scope: Scope,
+) -> Result<(), ApiError> {
+    let logged_in = identity.as_ref().and_then(discord_id);
+    if logged_in == Some(plan_id) {
+        return Ok(());
+    }
+    match api_keys.scopes(req) {
+        Some(scopes) if scopes.contains(&scope) => Ok(()),
+        Some(_) => Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "api key lacks the scope",
+        )),
+        None if logged_in.is_some() => Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "not allowed to access this plan",
+        )),
+        None => Err(ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "not logged in and no valid api key",
+        )),
+    }
+}
+
+#[get("/plans/{plan_id}")]
+async fn get_plan(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    identity: Option<Identity>,
+    api_keys: Data<ApiKeys>,
+    storage: Data<dyn Storage>,
+) -> Result<HttpResponse, ApiError> {
+    authorize_plan(&req, &identity, &api_keys, *plan_id, Scope::PlansRead)?;
+    let plan = plan_by_id(&plan_id, storage.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+#[post("/plans/{plan_id}")]
+async fn create_plan(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    body: Json<Value>,
+    identity: Option<Identity>,
+    api_keys: Data<ApiKeys>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    authorize_plan(&req, &identity, &api_keys, *plan_id, Scope::PlansWrite)?;
+    let plan = parse_plan(body.into_inner(), &sources)?;
+    let value = serde_json::to_value(&plan).map_err(ApiError::internal)?;
+    match storage.insert_plan(*plan_id, &value).await? {
+        false => Err(ApiError::new(StatusCode::CONFLICT, "plan already exists")),
+        true => Ok(HttpResponse::Created().json(plan)),
+    }
+}
+
+#[put("/plans/{plan_id}")]
+async fn put_plan(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    body: Json<Value>,
+    identity: Option<Identity>,
+    api_keys: Data<ApiKeys>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    authorize_plan(&req, &identity, &api_keys, *plan_id, Scope::PlansWrite)?;
+    let plan = parse_plan(body.into_inner(), &sources)?;
+    upsert_plan(&plan_id, &plan, storage.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+/// Applies a json merge patch (RFC 7396) to the stored plan.
+#[patch("/plans/{plan_id}")]
+async fn patch_plan(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    body: Json<Value>,
+    identity: Option<Identity>,
+    api_keys: Data<ApiKeys>,
+    storage: Data<dyn Storage>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    authorize_plan(&req, &identity, &api_keys, *plan_id, Scope::PlansWrite)?;
+    let stored = plan_by_id(&plan_id, storage.as_ref()).await?;
+    let mut value = serde_json::to_value(stored).map_err(ApiError::internal)?;
+    merge_patch(&mut value, &body);
+    let plan = parse_plan(value, &sources)?;
+    upsert_plan(&plan_id, &plan, storage.as_ref()).await?;
+    Ok(HttpResponse::Ok().json(plan))
+}
+
+#[delete("/plans/{plan_id}")]
+async fn delete_plan(
+    req: HttpRequest,
+    plan_id: Path<i64>,
+    identity: Option<Identity>,
+    api_keys: Data<ApiKeys>,
+    storage: Data<dyn Storage>,
+) -> Result<HttpResponse, ApiError> {
+    authorize_plan(&req, &identity, &api_keys, *plan_id, Scope::PlansWrite)?;
+    match storage.delete_user(*plan_id).await? {
+        false => Err(ApiError::not_found("plan not found")),
+        true => Ok(HttpResponse::NoContent().finish()),
+    }
+}
+
+fn merge_patch(target: &mut Value, patch: &Value) {
+    match patch {
+        Value::Object(patch) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            if let Value::Object(target) = target {
+                for (key, value) in patch {
+                    match value {
+                        Value::Null => {
+                            target.remove(key);
+                        }
+                        value => {
+                            merge_patch(target.entry(key.as_str()).or_insert(Value::Null), value)
+                        }
+                    }
+                }
+            }
+        }
+        patch => *target = patch.clone(),
+    }
+}
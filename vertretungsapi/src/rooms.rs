@@ -0,0 +1,214 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    *,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fs,
+};
+use vertretungscommon::vertretung::grid::PeriodGrid;
+use vertretungscommon::vertretung::vertretungsdings::{VDay, WEEKDAY_NAMES};
+
+use crate::api_error::ApiError;
+use crate::sources::Sources;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_free_rooms);
+}
+
+/// Regular occupancy of the rooms, loaded from the json file in
+/// `sources.<id>.rooms`. `occupied` maps a weekday to the occupied
+/// rooms by the number of the period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomPlan {
+    pub rooms: Vec<String>,
+    #[serde(default)]
+    pub occupied: HashMap<String, BTreeMap<i64, Vec<String>>>,
+}
+
+impl RoomPlan {
+    /// Loads the plan, its periods have to be part of the `grid` of the
+    /// source.
+    pub fn load(path: &str, grid: &PeriodGrid) -> Result<Self, Box<dyn Error>> {
+        let plan: RoomPlan = serde_json::from_str(&fs::read_to_string(path)?)?;
+        for (day, periods) in &plan.occupied {
+            if !WEEKDAY_NAMES.contains(&day.as_str()) {
+                return Err(format!("{path}: unknown weekday {day}").into());
+            }
+            if let Some(period) = periods.keys().find(|period| !grid.has_period(**period)) {
+                return Err(format!("{path}: period {period} of {day} is not in the grid").into());
+            }
+            for room in periods.values().flatten() {
+                if !plan.rooms.contains(room) {
+                    return Err(format!("{path}: {day} uses {room} which is not in rooms").into());
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Rooms without a lesson in the period, after applying the
+    /// cancellations and room changes of the day's substitutions. A row of
+    /// the substitution pages stands for the whole block of its period.
+    pub fn free_rooms(
+        &self,
+        date: &NaiveDate,
+        period: i64,
+        vdays: &[VDay],
+        grid: &PeriodGrid,
+    ) -> Vec<String> {
+        let day_name = WEEKDAY_NAMES[date.weekday().num_days_from_monday() as usize];
+        let mut occupied: Vec<String> = self
+            .occupied
+            .get(day_name)
+            .and_then(|periods| periods.get(&period))
+            .cloned()
+            .unwrap_or_default();
+
+        let lessons = vdays
+            .iter()
+            .filter(|vday| vday.date() == Some(*date))
+            .flat_map(|vday| vday.lessons())
+            .filter(|lesson| grid.block_of(lesson.time).contains(period));
+        for lesson in lessons {
+            let (freed, taken) = lesson.room_changes();
+            occupied.retain(|room| !freed.iter().any(|f| f.eq_ignore_ascii_case(room)));
+            occupied.extend(taken);
+        }
+
+        self.rooms
+            .iter()
+            .filter(|room| !occupied.iter().any(|o| o.eq_ignore_ascii_case(room)))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct FreeRoomsQuery {
+    period: i64,
+    date: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+struct FreeRooms {
+    date: NaiveDate,
+    period: i64,
+    rooms: Vec<String>,
+}
+
+#[get("/sources/{source}/rooms/free")]
+async fn get_free_rooms(
+    source: Path<String>,
+    query: Query<FreeRoomsQuery>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    let source = sources
+        .get(&source)
+        .ok_or(ApiError::not_found("unknown source"))?;
+    let room_plan = source.rooms.as_ref().ok_or(ApiError::not_found(
+        "no room plan configured for this source",
+    ))?;
+
+    if !source.grid.has_period(query.period) {
+        return Err(ApiError::bad_request(
+            "period is not part of the grid of the source",
+        ));
+    }
+    let date = query.date.unwrap_or_else(|| Utc::now().naive_utc().date());
+    if date.weekday().num_days_from_monday() >= 5 {
+        return Err(ApiError::bad_request("there are no lessons on weekends"));
+    }
+
+    let vdays = source
+        .vdays
+        .try_lock()
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    Ok(HttpResponse::Ok().json(FreeRooms {
+        date,
+        period: query.period,
+        rooms: room_plan.free_rooms(&date, query.period, &vdays, &source.grid),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoomPlan;
+    use crate::create_weeks_list::WeekZyklusList;
+    use crate::vertretundsdings::vertretungsdings::get_vday;
+    use chrono::NaiveDate;
+    use vertretungscommon::vertretung::grid::PeriodGrid;
+    use vertretungscommon::vertretung::vertretungsdings::VDay;
+
+    fn plan() -> RoomPlan {
+        serde_json::from_str(
+            r#"{"rooms": ["A1", "B12", "C3"], "occupied": {"Montag": {"1": ["A1"], "2": ["A1"]}}}"#,
+        )
+        .unwrap()
+    }
+
+    /// The substitutions of Monday 19.10.2026 with the rows of its table.
+    fn monday(rows: &str) -> Vec<VDay> {
+        let page = format!(
+            r#"<html><body>
+<h1 class="list-table-caption">Montag 19.10.2026</h1>
+<table class="table"><tbody>{rows}</tbody></table>
+</body></html>"#
+        );
+        let mut last_date = NaiveDate::MIN;
+        get_vday(&page, &mut last_date, &WeekZyklusList::default())
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn periods_are_looked_up_by_their_number() {
+        let plan: RoomPlan = serde_json::from_str(
+            r#"{"rooms": ["A1", "B12"], "occupied": {"Montag": {"11": ["A1"], "12": ["B12"]}}}"#,
+        )
+        .unwrap();
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let grid = PeriodGrid::default();
+        assert_eq!(plan.free_rooms(&monday, 11, &[], &grid), vec!["B12"]);
+        assert_eq!(plan.free_rooms(&monday, 1, &[], &grid), vec!["A1", "B12"]);
+    }
+
+    #[test]
+    fn cancelled_lesson_frees_its_room_in_the_whole_block() {
+        let vdays = monday(
+            "<tr><td>TIG21</td><td>1</td><td>Ma</td><td>A1</td><td>MÜL</td><td>Entfall</td><td></td></tr>
+             <tr><td>TIG21</td><td>2</td><td>Ma</td><td>A1</td><td>MÜL</td><td>Entfall</td><td></td></tr>",
+        );
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let grid = PeriodGrid::default();
+        for period in [1, 2] {
+            assert_eq!(
+                plan().free_rooms(&date, period, &vdays, &grid),
+                vec!["A1", "B12", "C3"]
+            );
+        }
+    }
+
+    #[test]
+    fn room_change_occupies_the_new_room_in_the_whole_block() {
+        let vdays = monday(
+            "<tr><td>TIG21</td><td>1</td><td>Ma</td><td><s>A1</s> C3</td><td>MÜL</td><td>Raumänderung</td><td></td></tr>
+             <tr><td>TIG21</td><td>2</td><td>Ma</td><td><s>A1</s> C3</td><td>MÜL</td><td>Raumänderung</td><td></td></tr>",
+        );
+        let date = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let grid = PeriodGrid::default();
+        for period in [1, 2] {
+            assert_eq!(
+                plan().free_rooms(&date, period, &vdays, &grid),
+                vec!["A1", "B12"]
+            );
+        }
+        assert_eq!(
+            plan().free_rooms(&date, 3, &vdays, &grid),
+            vec!["A1", "B12", "C3"]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use vertretungscommon::vertretung::grid::PeriodGrid;
+use vertretungscommon::vertretung::vertretungsdings::Plan;
+
+use crate::check_loop::init_vday_cache;
+use crate::config::SourceConfig;
+use crate::create_weeks_list::{create_weeks_list, WeekZyklusList};
+use crate::rooms::RoomPlan;
+use crate::stundenraster::Stundenraster;
+use crate::vertretundsdings::revocations::RevocationLog;
+use crate::{UpdatedList, VdayCache};
+
+/// Substitutions and Blockplan weeks of one department.
+pub struct Source {
+    pub vdays: Arc<VdayCache>,
+    pub updated_list: Arc<UpdatedList>,
+    /// substitutions and days taken back, for notifying about them
+    pub revocations: Arc<Mutex<RevocationLog>>,
+    pub weeks: Arc<Mutex<WeekZyklusList>>,
+    pub rooms: Option<RoomPlan>,
+    pub grid: PeriodGrid,
+    pub raster: Stundenraster,
+}
+
+/// All configured sources by their id.
+pub struct Sources(BTreeMap<String, Source>);
+
+impl Sources {
+    /// Loads the Blockplan weeks of every source and starts its check loop.
+    pub async fn init(
+        configs: &BTreeMap<String, SourceConfig>,
+    ) -> Result<(Self, Vec<(JoinHandle<()>, CancellationToken)>), Box<dyn Error>> {
+        let mut sources = BTreeMap::new();
+        let mut loops = Vec::new();
+        for (id, config) in configs {
+            let weeks = create_weeks_list(&config.blockplan)
+                .await
+                .map_err(|err| format!("source {id}: {err}"))?;
+            let rooms = match &config.rooms {
+                Some(path) => Some(
+                    RoomPlan::load(path, &config.grid)
+                        .map_err(|err| format!("source {id}: {err}"))?,
+                ),
+                None => None,
+            };
+            let raster = Stundenraster::load(config.stundenraster.as_deref(), &config.grid)
+                .map_err(|err| format!("source {id}: stundenraster: {err}"))?;
+            let (vdays, updated_list, revocations, handle, cancel_token) =
+                init_vday_cache(&weeks, &config.scraper);
+            sources.insert(
+                id.clone(),
+                Source {
+                    vdays,
+                    updated_list,
+                    revocations,
+                    weeks,
+                    rooms,
+                    grid: config.grid.clone(),
+                    raster,
+                },
+            );
+            loops.push((handle, cancel_token));
+        }
+        Ok((Sources(sources), loops))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Source> {
+        self.0.get(id)
+    }
+
+    /// A stored plan can name a source which was removed from the config.
+    pub fn of_plan(&self, plan: &Plan) -> Result<&Source, String> {
+        self.get(&plan.source)
+            .ok_or(format!("unknown source {}", plan.source))
+    }
+
+    pub fn ids(&self) -> Vec<&String> {
+        self.0.keys().collect()
+    }
+}
@@ -0,0 +1,149 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, error::Error, fs};
+use vertretungscommon::vertretung::grid::PeriodGrid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Period {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+/// Bell schedule mapping the period numbers to clock times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "StoredRaster")]
+pub struct Stundenraster(BTreeMap<i64, Period>);
+
+/// The times by period number, or a list of the times of the periods
+/// from 1 on like in older files. Untagged maps only keep string keys.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredRaster {
+    Periods(BTreeMap<String, Period>),
+    List(Vec<Period>),
+}
+
+impl TryFrom<StoredRaster> for Stundenraster {
+    type Error = String;
+
+    fn try_from(stored: StoredRaster) -> Result<Self, Self::Error> {
+        match stored {
+            StoredRaster::Periods(periods) => periods
+                .into_iter()
+                .map(|(period, time)| match period.parse() {
+                    Ok(period) => Ok((period, time)),
+                    Err(_) => Err(format!("{period} is no period number")),
+                })
+                .collect::<Result<_, _>>()
+                .map(Stundenraster),
+            StoredRaster::List(list) => Ok(Stundenraster((1..).zip(list).collect())),
+        }
+    }
+}
+
+impl Stundenraster {
+    /// Loads the schedule from the json file at `path`, falling back to the
+    /// default times of the BSZ. Every period of `grid` needs a time.
+    pub fn load(path: Option<&str>, grid: &PeriodGrid) -> Result<Self, Box<dyn Error>> {
+        let raster = match path {
+            Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+            None => Self::default(),
+        };
+        raster.check(grid)?;
+        Ok(raster)
+    }
+
+    fn check(&self, grid: &PeriodGrid) -> Result<(), String> {
+        let missing: Vec<String> = grid
+            .blocks
+            .iter()
+            .flat_map(|block| block.from..=block.to)
+            .filter(|period| !self.0.contains_key(period))
+            .map(|period| period.to_string())
+            .collect();
+        match missing.is_empty() {
+            true => Ok(()),
+            false => Err(format!("no times for the periods {}", missing.join(", "))),
+        }
+    }
+
+    pub fn get(&self, period: i64) -> Option<&Period> {
+        self.0.get(&period)
+    }
+}
+
+impl Default for Stundenraster {
+    fn default() -> Self {
+        let times = [
+            ((7, 30), (8, 15)),
+            ((8, 15), (9, 0)),
+            ((9, 20), (10, 5)),
+            ((10, 5), (10, 50)),
+            ((11, 20), (12, 5)),
+            ((12, 5), (12, 50)),
+            ((13, 5), (13, 50)),
+            ((13, 50), (14, 35)),
+            ((14, 45), (15, 30)),
+            ((15, 30), (16, 15)),
+        ];
+        Stundenraster(
+            (1..)
+                .zip(times)
+                .map(|(period, ((sh, sm), (eh, em)))| {
+                    let time = Period {
+                        start: NaiveTime::from_hms_opt(sh, sm, 0).unwrap(),
+                        end: NaiveTime::from_hms_opt(eh, em, 0).unwrap(),
+                    };
+                    (period, time)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use vertretungscommon::vertretung::grid::{PeriodGrid, PeriodRange};
+
+    use super::Stundenraster;
+
+    #[test]
+    fn lists_start_with_the_first_period() {
+        let raster: Stundenraster = serde_json::from_value(json!([
+            { "start": "07:30:00", "end": "08:15:00" },
+            { "start": "08:15:00", "end": "09:00:00" },
+        ]))
+        .unwrap();
+        assert!(raster.get(0).is_none());
+        assert_eq!(raster.get(2).unwrap().start.to_string(), "08:15:00");
+    }
+
+    #[test]
+    fn periods_outside_of_one_to_ten_have_times() {
+        let raster: Stundenraster = serde_json::from_value(json!({
+            "0": { "start": "06:45:00", "end": "07:30:00" },
+            "11": { "start": "16:15:00", "end": "17:00:00" },
+        }))
+        .unwrap();
+        assert_eq!(raster.get(0).unwrap().end.to_string(), "07:30:00");
+        assert_eq!(raster.get(11).unwrap().start.to_string(), "16:15:00");
+
+        let grid = PeriodGrid {
+            blocks: vec![PeriodRange::single(0), PeriodRange::single(11)],
+        };
+        assert!(raster.check(&grid).is_ok());
+    }
+
+    #[test]
+    fn every_period_of_the_grid_needs_a_time() {
+        let mut grid = PeriodGrid::default();
+        assert!(Stundenraster::default().check(&grid).is_ok());
+
+        grid.blocks.push(PeriodRange { from: 11, to: 12 });
+        assert_eq!(
+            Stundenraster::default().check(&grid),
+            Err("no times for the periods 11, 12".to_string())
+        );
+    }
+}
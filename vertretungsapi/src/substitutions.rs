@@ -0,0 +1,155 @@
+use actix_web::{
+    web::{self, Data, Path, Query},
+    *,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use vertretungscommon::classes::ClassSet;
+use vertretungscommon::vertretung::kinds::SubstitutionKind;
+use vertretungscommon::vertretung::vertretungsdings::{Lesson, VDay};
+
+use crate::api_error::ApiError;
+use crate::sources::Sources;
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_substitutions);
+}
+
+/// Filters of `/substitutions`, all given ones have to match.
+/// Values are compared case-insensitively, a trailing `*` matches
+/// every value starting with the rest, e.g. `teacher=Mül*`. `kind` is one
+/// of the classified kinds like `cancellation`, `type` the raw Art column.
+/// `teacher` and `room` match each name of the cell, struck out ones too.
+#[derive(Debug, Deserialize)]
+pub struct SubstitutionQuery {
+    class: Option<String>,
+    teacher: Option<String>,
+    room: Option<String>,
+    date: Option<NaiveDate>,
+    #[serde(rename = "type")]
+    vtype: Option<String>,
+    kind: Option<SubstitutionKind>,
+}
+
+impl SubstitutionQuery {
+    fn matches_day(&self, vday: &VDay) -> bool {
+        self.date.is_none_or(|date| vday.date() == Some(date))
+    }
+
+    fn matches_lesson(&self, lesson: &Lesson) -> bool {
+        // a substitution can list several classes, e.g. "TIG21-23"
+        let classes = ClassSet::parse(&lesson.class);
+        matches(&self.class, |pattern| {
+            classes.classes().any(|class| pattern.matches(class))
+        }) && matches(&self.teacher, |pattern| {
+            // the absent teacher struck out next to the substitute
+            lesson
+                .teachers()
+                .iter()
+                .any(|teacher| pattern.matches(teacher))
+        }) && matches(&self.room, |pattern| {
+            let (freed, taken) = lesson.room_changes();
+            freed.iter().chain(&taken).any(|room| pattern.matches(room))
+        }) && matches(&self.vtype, |pattern| pattern.matches(&lesson.vtype))
+            && self.kind.is_none_or(|kind| lesson.kind == Some(kind))
+    }
+}
+
+fn matches(filter: &Option<String>, f: impl Fn(Pattern) -> bool) -> bool {
+    filter
+        .as_deref()
+        .is_none_or(|filter| f(Pattern::new(filter)))
+}
+
+enum Pattern {
+    Exact(String),
+    Prefix(String),
+}
+
+impl Pattern {
+    fn new(filter: &str) -> Self {
+        let filter = filter.trim().to_lowercase();
+        match filter.strip_suffix('*') {
+            Some(prefix) => Pattern::Prefix(prefix.to_string()),
+            None => Pattern::Exact(filter),
+        }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        let value = value.trim().to_lowercase();
+        match self {
+            Pattern::Exact(filter) => value == *filter,
+            Pattern::Prefix(prefix) => value.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// The substitution days of a source with only the matching lessons,
+/// days without any are left out.
+#[get("/sources/{source}/substitutions")]
+async fn get_substitutions(
+    source: Path<String>,
+    query: Query<SubstitutionQuery>,
+    sources: Data<Sources>,
+) -> Result<HttpResponse, ApiError> {
+    let source = sources
+        .get(&source)
+        .ok_or(ApiError::not_found("unknown source"))?;
+    let vdays = source
+        .vdays
+        .try_lock()
+        .map_err(|err| ApiError::internal(err.to_string()))?;
+    let days: Vec<VDay> = vdays
+        .iter()
+        .filter(|vday| query.matches_day(vday))
+        .map(|vday| vday.filtered(|lesson| query.matches_lesson(lesson)))
+        .filter(|vday| !vday.lessons().is_empty())
+        .collect();
+    Ok(HttpResponse::Ok().json(days))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{Lesson, SubstitutionQuery};
+
+    fn query(teacher: Option<&str>, room: Option<&str>) -> SubstitutionQuery {
+        SubstitutionQuery {
+            class: None,
+            teacher: teacher.map(str::to_string),
+            room: room.map(str::to_string),
+            date: None,
+            vtype: None,
+            kind: None,
+        }
+    }
+
+    #[test]
+    fn teacher_and_room_match_struck_out_names() {
+        let lesson: Lesson = serde_json::from_value(json!({
+            "class": "TIG21",
+            "time": 3,
+            "subject": "Ma",
+            "room": "B12",
+            "teacher": "SCH",
+            "struck": { "room": "A1", "teacher": "MÜL" },
+            "vtype": "Vertretung",
+            "message": "",
+            "kind": "substitute",
+        }))
+        .unwrap();
+
+        for teacher in ["SCH", "mül", "Mü*"] {
+            assert!(
+                query(Some(teacher), None).matches_lesson(&lesson),
+                "{teacher}"
+            );
+        }
+        for room in ["B12", "a1"] {
+            assert!(query(None, Some(room)).matches_lesson(&lesson), "{room}");
+        }
+        assert!(!query(Some("SC"), None).matches_lesson(&lesson));
+        assert!(!query(None, Some("A1 B12")).matches_lesson(&lesson));
+    }
+}
@@ -1 +1,4 @@
-pub mod vertretungsdings;
\ No newline at end of file
+pub mod notes;
+pub mod revocations;
+pub mod validation;
+pub mod vertretungsdings;
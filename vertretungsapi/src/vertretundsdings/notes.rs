@@ -0,0 +1,91 @@
+use itertools::Itertools;
+use scraper::{ElementRef, Html, Selector};
+use vertretungscommon::vertretung::notes::DayNotes;
+
+/// The notes of the page from all tables but the `substitutions` table, if
+/// it has one. A row labeled like `Abwesende Lehrer` goes into its list,
+/// the other rows are messages only in the table headed `Nachrichten
+/// zum Tag`, text outside of it like the header and footer isn't one.
+pub fn extract_notes(doc: &Html, substitutions: Option<&ElementRef>) -> DayNotes {
+    let table_selection = Selector::parse("table").unwrap();
+    let row_selection = Selector::parse("tr").unwrap();
+    let cell_selection = Selector::parse("td, th").unwrap();
+
+    let mut notes = DayNotes::default();
+    let tables = doc.select(&table_selection).filter(|table| {
+        substitutions.is_none_or(|substitutions| {
+            table.id() != substitutions.id()
+                && !table.ancestors().any(|a| a.id() == substitutions.id())
+        })
+    });
+    for table in tables {
+        let mut in_messages = false;
+        for row in table.select(&row_selection) {
+            let cells: Vec<String> = row
+                .select(&cell_selection)
+                .map(|cell| text(&cell))
+                .filter(|cell| !cell.is_empty())
+                .collect();
+            let (label, rest) = match cells.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            match list_of(&mut notes, label) {
+                Some(list) => list.extend(split_list(&rest.join(", "))),
+                _ if is_heading(&cells) => in_messages = true,
+                _ if in_messages => notes.messages.push(cells.join(" ")),
+                _ => {}
+            }
+        }
+    }
+    notes
+}
+
+/// The list of `notes` a row labeled `label` goes into.
+fn list_of<'a>(notes: &'a mut DayNotes, label: &str) -> Option<&'a mut Vec<String>> {
+    let label = label.trim_end_matches(':').to_lowercase();
+    if label.starts_with("abwesende lehrer") {
+        Some(&mut notes.absent_teachers)
+    } else if label.starts_with("abwesende klassen") {
+        Some(&mut notes.absent_classes)
+    } else if label.starts_with("blockierte räume") {
+        Some(&mut notes.blocked_rooms)
+    } else {
+        None
+    }
+}
+
+/// The heading row of the messages table, the rows after it are messages.
+fn is_heading(cells: &[String]) -> bool {
+    cells.len() == 1 && cells[0].trim_end_matches(':').to_lowercase() == "nachrichten zum tag"
+}
+
+/// The text of an element with whitespace, non-breaking spaces included,
+/// collapsed.
+fn text(element: &ElementRef) -> String {
+    element.text().join(" ").split_whitespace().join(" ")
+}
+
+/// The entries of a list like `MÜL (1-4), SCH (5, 6)`, commas in
+/// parentheses don't separate entries.
+fn split_list(list: &str) -> Vec<String> {
+    let mut entries = vec![String::new()];
+    let mut depth = 0;
+    for c in list.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                entries.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        entries.last_mut().unwrap().push(c);
+    }
+    entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
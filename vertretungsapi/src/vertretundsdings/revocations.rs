@@ -0,0 +1,143 @@
+use chrono::{NaiveDate, Utc};
+use vertretungscommon::vertretung::revocations::Revocation;
+use vertretungscommon::vertretung::vertretungsdings::{Lesson, VDay};
+
+/// The revocations of a source for the days which are not over yet.
+#[derive(Debug)]
+pub struct RevocationLog {
+    next_id: u64,
+    revocations: Vec<Revocation>,
+}
+
+impl Default for RevocationLog {
+    /// Ids start at the current time in milliseconds, so they keep
+    /// increasing over restarts of the api.
+    fn default() -> Self {
+        RevocationLog {
+            next_id: Utc::now().timestamp_millis() as u64,
+            revocations: Vec::new(),
+        }
+    }
+}
+
+impl RevocationLog {
+    /// Records what of `old` is taken back in `new`. A substitution is
+    /// when no row for its lesson is left or a cancellation is no longer
+    /// one, a whole day when it is gone before it took place. Returns
+    /// whether there was anything.
+    pub fn record(&mut self, old: &[VDay], new: &[VDay], today: NaiveDate) -> bool {
+        let before = self.revocations.len();
+        for old_day in old {
+            let date = match old_day.date() {
+                Some(date) if date >= today => date,
+                _ => continue,
+            };
+            let new_day = new.iter().find(|new_day| new_day.date() == Some(date));
+            match new_day {
+                Some(new_day) => {
+                    for lesson in old_day.lessons() {
+                        let mut remaining = new_day
+                            .lessons()
+                            .iter()
+                            .filter(|new| new.same_slot(lesson))
+                            .peekable();
+                        let revoked = match lesson.is_cancelled() {
+                            true => !remaining.any(Lesson::is_cancelled),
+                            false => remaining.peek().is_none(),
+                        };
+                        if revoked {
+                            self.push(old_day.day(), date, Some(lesson.clone()));
+                        }
+                    }
+                }
+                // the page of today goes away once it is over
+                None if date > today => self.push(old_day.day(), date, None),
+                None => {}
+            }
+        }
+        let recorded = self.revocations.len() > before;
+        self.revocations
+            .retain(|revocation| revocation.date >= today);
+        recorded
+    }
+
+    fn push(&mut self, day: &str, date: NaiveDate, lesson: Option<Lesson>) {
+        self.next_id += 1;
+        self.revocations.push(Revocation {
+            id: self.next_id,
+            day: day.to_string(),
+            date,
+            lesson,
+        });
+    }
+
+    /// The revocations after the one with id `since`, all without it.
+    pub fn since(&self, since: Option<u64>) -> Vec<Revocation> {
+        self.revocations
+            .iter()
+            .filter(|revocation| since.is_none_or(|since| revocation.id > since))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RevocationLog;
+    use chrono::NaiveDate;
+    use serde_json::json;
+    use vertretungscommon::vertretung::vertretungsdings::VDay;
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    /// Monday with the lessons `(period, subject, kind)` of TIG21.
+    fn day(lessons: &[(i64, &str, &str)]) -> VDay {
+        let lessons: Vec<_> = lessons
+            .iter()
+            .map(|(time, subject, kind)| {
+                json!({
+                    "class": "TIG21", "time": time, "subject": subject, "room": "A1",
+                    "teacher": "MÜL", "vtype": "", "message": "", "kind": kind,
+                })
+            })
+            .collect();
+        serde_json::from_value(json!(["Montag 19.10.2026", "I", lessons, {}])).unwrap()
+    }
+
+    #[test]
+    fn changed_rows_of_a_lesson_are_no_revocation() {
+        let mut log = RevocationLog::default();
+        let old = [day(&[(3, "Ma", "substitute")])];
+        let new = [day(&[(3, "Ma", "room_change")])];
+        assert!(!log.record(&old, &new, monday()));
+
+        let old = [day(&[(3, "Ma", "cancellation")])];
+        let new = [day(&[(3, "Ma", "cancellation"), (4, "Ma", "cancellation")])];
+        assert!(!log.record(&old, &new, monday()));
+    }
+
+    #[test]
+    fn gone_rows_and_lifted_cancellations_are_revoked() {
+        let mut log = RevocationLog::default();
+        let old = [day(&[(3, "Ma", "cancellation"), (5, "De", "substitute")])];
+        let new = [day(&[(3, "Ma", "substitute")])];
+        assert!(log.record(&old, &new, monday()));
+        let revoked: Vec<_> = log
+            .since(None)
+            .into_iter()
+            .map(|revocation| revocation.lesson.unwrap().time)
+            .collect();
+        assert_eq!(revoked, vec![3, 5]);
+    }
+
+    #[test]
+    fn only_days_after_today_are_withdrawn() {
+        let mut log = RevocationLog::default();
+        let old = [day(&[(3, "Ma", "cancellation")])];
+        assert!(!log.record(&old, &[], monday()));
+        assert!(log.record(&old, &[], monday().pred_opt().unwrap()));
+        assert!(log.since(None)[0].lesson.is_none());
+    }
+}
@@ -0,0 +1,310 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use vertretungscommon::courses::{CourseSelector, MatchMode};
+use vertretungscommon::migration::migrate_plan_value;
+use vertretungscommon::vertretung::grid::PeriodGrid;
+use vertretungscommon::vertretung::vertretungsdings::{Plan, PlanBlock, PlanKind, WEEKDAY_NAMES};
+
+pub const PLAN_SCHEMA: &str = include_str!("../../schema/plan.schema.json");
+
+/// A problem with a plan and the json path where it was found.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanError {
+    pub path: String,
+    pub message: String,
+}
+
+impl PlanError {
+    pub fn new(path: impl ToString, message: impl ToString) -> Self {
+        PlanError {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Upgrades a json plan of any version and checks its shape before
+/// deserializing it, so that every problem is reported with its path
+/// instead of the first serde error, and then validates the plan itself.
+pub fn validate_plan_value(value: &Value) -> Result<Plan, Vec<PlanError>> {
+    let value =
+        &migrate_plan_value(value.clone()).map_err(|err| vec![PlanError::new("version", err)])?;
+
+    let mut errors = Vec::new();
+
+    let root = match value.as_object() {
+        Some(root) => root,
+        None => return Err(vec![PlanError::new("$", "plan has to be an object")]),
+    };
+
+    if !root.get("source").is_some_and(Value::is_string) {
+        errors.push(PlanError::new("source", "has to be a string"));
+    }
+
+    // plans without a kind are class plans
+    let kind = root.get("kind");
+    let teacher_plan = kind.and_then(Value::as_str) == Some("teacher");
+    if !kind.is_none_or(|kind| matches!(kind.as_str(), Some("class") | Some("teacher"))) {
+        errors.push(PlanError::new("kind", "has to be \"class\" or \"teacher\""));
+    }
+
+    // teacher plans may leave out the class fields, class plans the teacher
+    if !root
+        .get("group")
+        .is_none_or(|group| group.is_string() || group.is_null())
+    {
+        errors.push(PlanError::new("group", "has to be a string"));
+    }
+
+    for (field, required) in [("class_name", !teacher_plan), ("teacher", teacher_plan)] {
+        match root.get(field) {
+            Some(Value::String(_)) => {}
+            None if !required => {}
+            _ => errors.push(PlanError::new(field, "has to be a string")),
+        }
+    }
+
+    match root.get("subjects") {
+        Some(Value::Array(subjects)) => {
+            for (i, subject) in subjects.iter().enumerate() {
+                if let Err(err) = serde_json::from_value::<CourseSelector>(subject.clone()) {
+                    errors.push(PlanError::new(format!("subjects[{i}]"), err));
+                }
+            }
+        }
+        None if teacher_plan => {}
+        _ => errors.push(PlanError::new("subjects", "has to be an array")),
+    }
+
+    match root.get("days").and_then(Value::as_array) {
+        Some(days) => {
+            for (i, day) in days.iter().enumerate() {
+                check_day_value(&format!("days[{i}]"), day, &mut errors);
+            }
+        }
+        None => errors.push(PlanError::new("days", "has to be an array")),
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let plan: Plan = serde_json::from_value(value.clone())
+        .map_err(|err| vec![PlanError::new("$", err.to_string())])?;
+    validate_plan(&plan)?;
+    Ok(plan)
+}
+
+fn check_day_value(path: &str, day: &Value, errors: &mut Vec<PlanError>) {
+    if !day.get("day").is_some_and(Value::is_string) {
+        errors.push(PlanError::new(format!("{path}.day"), "has to be a string"));
+    }
+    let lessons = match day.get("lessons").and_then(Value::as_array) {
+        Some(lessons) => lessons,
+        None => {
+            errors.push(PlanError::new(
+                format!("{path}.lessons"),
+                "has to be an array",
+            ));
+            return;
+        }
+    };
+    for (i, lesson) in lessons.iter().enumerate() {
+        if let Err(err) = serde_json::from_value::<PlanBlock>(lesson.clone()) {
+            errors.push(PlanError::new(format!("{path}.lessons[{i}]"), err));
+        }
+    }
+}
+
+/// Checks the plan itself, that its fields are not empty, its days are
+/// weekdays and its lessons don't overlap.
+pub fn validate_plan(plan: &Plan) -> Result<(), Vec<PlanError>> {
+    let mut errors = Vec::new();
+
+    if plan.source.trim().is_empty() {
+        errors.push(PlanError::new("source", "must not be empty"));
+    }
+
+    match plan.kind {
+        PlanKind::Class => {
+            if plan.class_name.trim().is_empty() {
+                errors.push(PlanError::new("class_name", "must not be empty"));
+            }
+            if plan
+                .class_name
+                .contains(|c: char| ",;/-".contains(c) || c.is_whitespace())
+            {
+                errors.push(PlanError::new(
+                    "class_name",
+                    "has to be a single class, the group goes into group",
+                ));
+            }
+            if plan.class_name.trim_end_matches('*').contains('*') {
+                errors.push(PlanError::new("class_name", "* is only allowed at the end"));
+            }
+            if plan
+                .group
+                .as_ref()
+                .is_some_and(|group| group.trim().is_empty())
+            {
+                errors.push(PlanError::new(
+                    "group",
+                    "must not be empty, leave it out instead",
+                ));
+            }
+            if plan.subjects.is_empty() {
+                errors.push(PlanError::new(
+                    "subjects",
+                    "must not be empty, no substitution would ever match",
+                ));
+            }
+        }
+        PlanKind::Teacher => {
+            if plan.teacher.trim().is_empty() {
+                errors.push(PlanError::new("teacher", "must not be empty"));
+            }
+        }
+    }
+    for (i, course) in plan.subjects.iter().enumerate() {
+        let path = format!("subjects[{i}]");
+        if course.subject.trim().is_empty() {
+            errors.push(PlanError::new(
+                format!("{path}.subject"),
+                "must not be empty, it would match every substitution",
+            ));
+        }
+        if course.mode == MatchMode::Regex {
+            if let Err(err) = course.regex() {
+                errors.push(PlanError::new(format!("{path}.subject"), err));
+            }
+        }
+        for (field, value) in [("teacher", &course.teacher), ("level", &course.level)] {
+            if value.as_ref().is_some_and(|value| value.trim().is_empty()) {
+                errors.push(PlanError::new(
+                    format!("{path}.{field}"),
+                    "must not be empty, leave it out instead",
+                ));
+            }
+        }
+    }
+
+    for (i, day) in plan.days.iter().enumerate() {
+        let path = format!("days[{i}]");
+        if !WEEKDAY_NAMES.contains(&day.day.as_str()) {
+            errors.push(PlanError::new(
+                format!("{path}.day"),
+                format!(
+                    "unknown weekday {:?}, expected one of {}",
+                    day.day,
+                    WEEKDAY_NAMES.join(", ")
+                ),
+            ));
+        }
+        if let Some(first) = plan.days[..i].iter().position(|other| other.day == day.day) {
+            errors.push(PlanError::new(
+                format!("{path}.day"),
+                format!("{} is already listed in days[{first}]", day.day),
+            ));
+        }
+        for (j, block) in day.lessons.iter().enumerate() {
+            let periods = block.periods;
+            let block_path = format!("{path}.lessons[{j}].periods");
+            if periods.from < 0 || periods.from > periods.to {
+                errors.push(PlanError::new(
+                    &block_path,
+                    "has to go from a lower to a higher period",
+                ));
+            }
+            if let Some(other) = day.lessons[..j]
+                .iter()
+                .position(|other| other.periods.overlaps(&periods))
+            {
+                errors.push(PlanError::new(
+                    &block_path,
+                    format!("{periods} overlaps with {path}.lessons[{other}]"),
+                ));
+            }
+        }
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Lessons must not go on over a break of the grid of their source.
+pub fn validate_grid(plan: &Plan, grid: &PeriodGrid) -> Result<(), Vec<PlanError>> {
+    let mut errors = Vec::new();
+    for (i, day) in plan.days.iter().enumerate() {
+        for (j, block) in day.lessons.iter().enumerate() {
+            if !grid.fits(&block.periods) {
+                errors.push(PlanError::new(
+                    format!("days[{i}].lessons[{j}].periods"),
+                    format!(
+                        "{} spans a break, the blocks are {}",
+                        block.periods,
+                        grid.blocks
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn class_plan() -> Value {
+        json!({
+            "version": 6,
+            "source": "bgy",
+            "class_name": "TIG21",
+            "subjects": [{ "subject": "Ma" }],
+            "days": []
+        })
+    }
+
+    #[test]
+    fn plans_without_a_kind_are_class_plans() {
+        let plan = validate_plan_value(&class_plan()).unwrap();
+        assert_eq!(plan.kind, PlanKind::Class);
+    }
+
+    #[test]
+    fn only_a_present_invalid_kind_is_rejected() {
+        let mut plan = class_plan();
+        plan["kind"] = json!("student");
+        let errors = validate_plan_value(&plan).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "kind");
+    }
+
+    #[test]
+    fn plans_before_sources_need_none() {
+        let plan = validate_plan_value(&json!({
+            "class_name": "TIG21",
+            "subjects": ["Ma"],
+            "days": []
+        }))
+        .unwrap();
+        assert_eq!(plan.source, "bgy");
+    }
+}
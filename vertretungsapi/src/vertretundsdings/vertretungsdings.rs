@@ -1,22 +1,30 @@
 use chrono::naive::NaiveDate;
-use futures::TryFutureExt;
+use chrono::{Datelike, Duration};
 use itertools::Itertools;
-use reqwest::Client;
-use scraper::{Html, Selector};
-use serde::{Deserialize, Serialize};
-use std::{
-    env,
-    sync::{Arc, Mutex},
+use log::warn;
+use reqwest::{Client, StatusCode};
+use scraper::{ElementRef, Html, Selector};
+use std::sync::{Arc, Mutex};
+use vertretungscommon::vertretung::grid::{PeriodGrid, PeriodRange};
+use vertretungscommon::vertretung::kinds::SubstitutionKind;
+use vertretungscommon::vertretung::moves::Move;
+use vertretungscommon::vertretung::vertretungsdings::{
+    Day, Lesson, Plan, Struck, VDay, WEEKDAY_NAMES,
 };
 
-use crate::create_weeks_list::{WeekZyklusList, Zyklus};
+use super::notes::extract_notes;
+use crate::config::ScraperConfig;
+use crate::create_weeks_list::WeekZyklusList;
 
 #[derive(Debug)]
 pub enum ChangeOption<T> {
     Some(T),
     Same(T),
     None,
+    /// the page does not exist
     End,
+    /// the page could not be read, it may still exist
+    Failed,
 }
 
 pub async fn check_change(
@@ -24,61 +32,54 @@ pub async fn check_change(
     last_time: &mut String,
     last_date: &mut NaiveDate,
     weeks_zykluses: Arc<Mutex<WeekZyklusList>>,
+    config: &ScraperConfig,
 ) -> ChangeOption<VDay> {
-    let url = format!(
-        "https://geschuetzt.bszet.de/s-lk-vw/Vertretungsplaene/V_PlanBGy/V_DC_00{}.html",
-        number
-    );
     let c = Client::new();
     let result = c
-        .get(url)
-        .basic_auth("bsz-et-2223", Some(env::var("PW").expect("no PW in env")))
+        .get(config.page_url(number))
+        .basic_auth(&config.user, config.password.as_ref())
         .send()
-        .map_ok(|e| {
-            if e.status().is_success() {
-                Option::Some(e)
-            } else {
-                Option::None
-            }
-        })
-        .await
-        .ok()
-        .flatten();
+        .await;
 
     let res = match result {
-        Some(r) => r,
-        None => return ChangeOption::End,
+        Ok(r) if r.status().is_success() => r,
+        Ok(r) if matches!(r.status(), StatusCode::NOT_FOUND | StatusCode::GONE) => {
+            return ChangeOption::End
+        }
+        _ => return ChangeOption::Failed,
     };
 
-    let headers = res.headers();
-    let this_time = headers
+    // pages without a readable last-modified always count as changed
+    let this_time = res
+        .headers()
         .get("last-modified")
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string();
+        .and_then(|time| time.to_str().ok())
+        .map(str::to_string);
 
-    let text = res.text().await.unwrap();
+    let text = match res.text().await {
+        Ok(text) => text,
+        Err(_) => return ChangeOption::Failed,
+    };
     let vday = if let Ok(weeks) = weeks_zykluses.try_lock() {
         match get_vday(&text, last_date, &weeks) {
             Some(vday) => vday,
             None => return ChangeOption::None,
         }
     } else {
-        return ChangeOption::None;
+        return ChangeOption::Failed;
     };
 
-    return match last_time.to_string().eq(this_time.as_str()) {
-        true => ChangeOption::Same(vday),
-        false => {
-            *last_time = this_time.to_string();
+    match this_time {
+        Some(this_time) if *last_time == this_time => ChangeOption::Same(vday),
+        this_time => {
+            *last_time = this_time.unwrap_or_default();
             ChangeOption::Some(vday)
         }
-    };
+    }
 }
 
 pub fn get_vday(
-    text: &String,
+    text: &str,
     last_date: &mut NaiveDate,
     weeks_zykluses: &WeekZyklusList,
 ) -> Option<VDay> {
@@ -88,9 +89,10 @@ pub fn get_vday(
     let date = doc
         .select(&date_selection)
         .next()?
-        .inner_html()
-        .trim()
-        .to_string();
+        .text()
+        .join(" ")
+        .split_whitespace()
+        .join(" ");
 
     let date_str = date.split_whitespace().last()?;
     let this_date = NaiveDate::parse_from_str(date_str, "%d.%m.%Y").ok()?;
@@ -109,205 +111,541 @@ pub fn get_vday(
 
     let mut v_lessons: Vec<Lesson> = Vec::new();
 
-    let table = doc.select(&table_body_selection).next()?;
-
-    for row in table.select(&table_row_selection) {
-        let fields = row.select(&table_field_selection);
-
-        let mut content_fields: Vec<String> = fields
-            .map(|item| item.inner_html().trim().to_string())
-            .collect();
-        if row.inner_html().contains("&nbsp;") {
-            let last_lesson = v_lessons.last()?.to_vec();
-            for (i, s) in &mut content_fields
-                .iter_mut()
-                .enumerate()
-                .filter(|(_i, s)| s.contains("&nbsp;"))
-            {
-                let replacement = last_lesson.get(i)?;
-                *s = replacement.to_string();
-            }
+    // other tables hold the notes, the substitutions are the one with rows
+    // of all seven columns, a day without any has none
+    let table = doc.select(&table_body_selection).find(|body| {
+        body.select(&table_row_selection)
+            .any(|row| row.select(&table_field_selection).count() >= 7)
+    });
+    let substitutions = table.and_then(|body| ElementRef::wrap(body.parent()?));
+    let notes = extract_notes(&doc, substitutions.as_ref());
+
+    let mut previous: Option<Vec<Cell>> = None;
+    let rows = table
+        .iter()
+        .flat_map(|body| body.select(&table_row_selection));
+    for row in rows {
+        let mut cells: Vec<Cell> = row.select(&table_field_selection).map(cell_text).collect();
+        if !fill_down(&mut cells, previous.as_deref()) {
+            warn!("{date}: continuation row without a row above, skipped");
+            continue;
         }
-
-        v_lessons.push(Lesson {
-            class: content_fields.get(0)?.into(),
-            time: content_fields.get(1)?.trim_end_matches('.').parse().ok()?,
-            subject: content_fields.get(2)?.into(),
-            room: content_fields.get(3)?.into(),
-            teacher: content_fields.get(4)?.into(),
-            vtype: content_fields.get(5)?.into(),
-            message: content_fields.get(6)?.into(),
-        });
+        match lesson_from_cells(&cells, this_date) {
+            Some(lesson) => v_lessons.push(lesson),
+            None => warn!("{date}: row {cells:?} is no substitution, skipped"),
+        }
+        previous = Some(cells);
     }
     v_lessons = v_lessons
         .into_iter()
-        .unique_by(Lesson::convert_to_compareable)
+        .unique_by(convert_to_compareable)
         .collect();
 
-    return Some(VDay(date, zyklus, v_lessons.into()));
+    Some(VDay(date, zyklus, v_lessons, notes))
 }
 
-pub fn get_day(VDay(day_str, zyklus, v_lessons): &VDay, plan: &Plan) -> Option<Day> {
-    let mut splits = day_str.split_whitespace().into_iter();
-    let day_name = splits.next()?;
+/// Builds the effective Monday–Friday timetable of the week containing `date`.
+/// Days with a published `VDay` get its substitutions, all other days are
+/// filled from the plan with the zyklus of the Blockplan. Days that are not
+/// part of any Blockplan week are marked as holidays.
+pub fn get_week(
+    date: &NaiveDate,
+    plan: &Plan,
+    vdays: &[VDay],
+    weeks_zykluses: &WeekZyklusList,
+    grid: &PeriodGrid,
+) -> Vec<Day> {
+    let monday = *date - Duration::days(date.weekday().num_days_from_monday() as i64);
+
+    WEEKDAY_NAMES
+        .iter()
+        .zip(0..)
+        .map(|(day_name, offset)| {
+            let this_date = monday + Duration::days(offset);
+            let vday = vdays.iter().find(|vday| vday.date() == Some(this_date));
+
+            let mut day = match vday {
+                Some(VDay(day_str, _, _, notes)) => Day {
+                    notes: notes.clone(),
+                    ..Day::new(day_str)
+                },
+                None => Day::new(&format!("{} {}", day_name, this_date.format("%d.%m.%Y"))),
+            };
+
+            let zyklus = match vday {
+                Some(VDay(_, zyklus, _, _)) => Some(*zyklus),
+                None => weeks_zykluses.get(&this_date),
+            };
+
+            match zyklus {
+                Some(zyklus) => {
+                    if let Some(plan_day) = plan.day(day_name) {
+                        day.add_regular(plan_day, &zyklus);
+                    }
+                    if let Some(VDay(_, _, v_lessons, _)) = vday {
+                        day.add_substitutions(v_lessons, plan, grid);
+                    }
+                    day.resolve(plan);
+                }
+                None => day.holiday = true,
+            }
+            day
+        })
+        .collect()
+}
 
-    let mut res_day: Day = Day::new(&day_str.to_string());
+/// The text of a table cell, the struck out original value apart from the
+/// rest, e.g. `B12` and `A1` for `<s>B12</s> A1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Cell {
+    current: String,
+    struck: String,
+}
 
-    for v_lesson in v_lessons.iter().filter(|item| {
-        item.class.contains(&plan.class_name) && is_in(&item.subject, &plan.subjects)
-    }) {
-        res_day
-            .lessons
-            .get_mut((v_lesson.time - 1) as usize)?
-            .push(v_lesson.clone());
+impl Cell {
+    fn is_empty(&self) -> bool {
+        self.current.is_empty() && self.struck.is_empty()
     }
 
-    let plan_day = plan.days.iter().find(|item| item.day.contains(day_name))?;
-
-    let empty_times = res_day
-        .lessons
-        .iter_mut()
-        .enumerate()
-        .filter(|(i, item)| i % 2 == 0 && item.len() == 0);
-
-    for (i, ls) in empty_times {
-        if let Some(normal) = plan_day.lessons.get(i / 2) {
-            match normal {
-                WeekOption::AandB(l) => ls.push(l.to_lesson()),
-                WeekOption::A(l) => match zyklus {
-                    Zyklus::I => ls.push(l.to_lesson()),
-                    Zyklus::II => (),
-                },
-                WeekOption::B(l) => match zyklus {
-                    Zyklus::II => ls.push(l.to_lesson()),
-                    Zyklus::I => (),
-                },
-                WeekOption::AorB(l1, l2) => match zyklus {
-                    Zyklus::I => ls.push(l1.to_lesson()),
-                    Zyklus::II => ls.push(l2.to_lesson()),
-                },
-                WeekOption::None => (),
-            }
-        }
+    /// All of the text, the struck out part first.
+    fn text(&self) -> String {
+        [self.struck.as_str(), self.current.as_str()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .join(" ")
     }
-    Some(res_day)
 }
 
-fn is_in(string: &str, vec: &Vec<String>) -> bool {
-    for s in vec {
-        if string.contains(s) {
-            return true;
+/// The text of a table cell with entities decoded, markup dropped and
+/// whitespace, non-breaking spaces included, collapsed.
+fn cell_text(cell: ElementRef) -> Cell {
+    let mut current = String::new();
+    let mut struck = String::new();
+    for node in cell.descendants() {
+        let part = match node.value().as_text() {
+            Some(part) => part,
+            None => continue,
+        };
+        let is_struck = node
+            .ancestors()
+            .take_while(|a| a.id() != cell.id())
+            .any(|a| {
+                a.value()
+                    .as_element()
+                    .is_some_and(|el| matches!(el.name(), "s" | "del" | "strike"))
+            });
+        match is_struck {
+            true => struck.push_str(part),
+            false => current.push_str(part),
         }
     }
-    return false;
+    Cell {
+        current: current.split_whitespace().join(" "),
+        struck: struck.split_whitespace().join(" "),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanLesson {
-    time: i64,
-    subject: String,
-    room: String,
-    teacher: String,
+/// Untis leaves the cells of a row empty that repeat the row above. A row
+/// with an empty class cell continues the previous row and takes all its
+/// empty cells from it, empty cells of other rows stay empty. `false` for a
+/// continuation row without a previous row.
+fn fill_down(cells: &mut [Cell], previous: Option<&[Cell]>) -> bool {
+    if cells.first().is_none_or(|class| !class.is_empty()) {
+        return true;
+    }
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return false,
+    };
+    for (cell, above) in cells.iter_mut().zip(previous) {
+        if cell.is_empty() {
+            *cell = above.clone();
+        }
+    }
+    true
 }
 
-impl PlanLesson {
-    pub fn to_lesson(&self) -> Lesson {
-        Lesson::new(
-            self.time,
-            self.subject.as_str(),
-            self.room.as_str(),
-            self.teacher.as_str(),
-        )
-    }
+/// Rows repeated for the same period, the rows of the other periods of a
+/// block are merged with the grid once the day is built.
+fn convert_to_compareable(
+    lesson: &Lesson,
+) -> (String, String, String, String, Struck, String, String, i64) {
+    (
+        lesson.class.to_string(),
+        lesson.subject.to_string(),
+        lesson.room.to_string(),
+        lesson.teacher.to_string(),
+        lesson.struck.clone(),
+        lesson.vtype.to_string(),
+        lesson.message.to_string(),
+        lesson.time,
+    )
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Lesson {
-    pub class: String,
-    pub time: i64,
-    pub subject: String,
-    pub room: String,
-    pub teacher: String,
-    pub vtype: String,
-    pub message: String,
+/// The substitution of a table row, its cells are class, period, subject,
+/// room, teacher, Art and message.
+fn lesson_from_cells(cells: &[Cell], date: NaiveDate) -> Option<Lesson> {
+    let (subject, room, teacher) = (cells.get(2)?, cells.get(3)?, cells.get(4)?);
+    let mut lesson = Lesson {
+        class: cells.first()?.text(),
+        time: cells.get(1)?.text().trim_end_matches('.').parse().ok()?,
+        subject: subject.current.clone(),
+        room: room.current.clone(),
+        teacher: teacher.current.clone(),
+        struck: Struck {
+            subject: subject.struck.clone(),
+            room: room.struck.clone(),
+            teacher: teacher.struck.clone(),
+        },
+        vtype: cells.get(5)?.text(),
+        message: cells.get(6)?.text(),
+        kind: None,
+        changes: Vec::new(),
+        moved: None,
+    };
+    lesson.moved = Move::parse(&lesson.message, date);
+    lesson.kind = Some(lesson.classify());
+    Some(lesson)
 }
+/// Links both ends of the moved lessons. The other end gets its date from
+/// the published day it falls on, and if that day has no substitution
+/// pointing back, one is added there so both days show the move.
+pub fn link_moves(vdays: &mut [VDay]) {
+    let dates: Vec<Option<NaiveDate>> = vdays.iter().map(VDay::date).collect();
+    let mut counterparts: Vec<(usize, PeriodRange, Lesson)> = Vec::new();
+
+    for (i, vday) in vdays.iter_mut().enumerate() {
+        let date = match dates[i] {
+            Some(date) => date,
+            None => continue,
+        };
+        // a page for a weekend has no weekday to point back to
+        let day = match WEEKDAY_NAMES.get(date.weekday().num_days_from_monday() as usize) {
+            Some(day) => day.to_string(),
+            None => continue,
+        };
+        for lesson in vday.2.iter_mut() {
+            let moved = match lesson.moved.as_mut() {
+                Some(moved) => moved,
+                None => continue,
+            };
+            // without a date in the message the closest day with that name
+            let other = dates
+                .iter()
+                .enumerate()
+                .filter_map(|(j, other)| Some((j, (*other)?)))
+                .filter(|(_, other)| match moved.date {
+                    Some(date) => *other == date,
+                    None => {
+                        WEEKDAY_NAMES.get(other.weekday().num_days_from_monday() as usize)
+                            == Some(&moved.day.as_str())
+                    }
+                })
+                .min_by_key(|(_, other)| (*other - date).num_days().abs());
+            let (j, other_date) = match other {
+                Some(other) => other,
+                None => continue,
+            };
+            moved.date = Some(other_date);
+
+            let counterpart = Lesson {
+                class: lesson.class.clone(),
+                time: moved.periods.from,
+                subject: lesson.subject.clone(),
+                room: lesson.room.clone(),
+                teacher: lesson.teacher.clone(),
+                struck: lesson.struck.clone(),
+                vtype: String::new(),
+                message: String::new(),
+                kind: Some(SubstitutionKind::Moved),
+                changes: Vec::new(),
+                moved: Some(Move {
+                    kind: moved.kind.reverse(),
+                    day: day.clone(),
+                    date: Some(date),
+                    periods: PeriodRange::single(lesson.time),
+                }),
+            };
+            counterparts.push((j, moved.periods, counterpart));
+        }
+    }
 
-impl Lesson {
-    fn new(time: i64, subject: &str, room: &str, teacher: &str) -> Lesson {
-        Lesson {
-            class: String::new(),
-            time,
-            subject: subject.to_string(),
-            room: room.to_string(),
-            teacher: teacher.to_string(),
-            vtype: String::new(),
-            message: String::new(),
+    for (j, periods, mut counterpart) in counterparts {
+        let back = counterpart.moved.clone().expect("counterparts point back");
+        let points_back = |lesson: &Lesson| {
+            periods.contains(lesson.time)
+                && lesson.class == counterpart.class
+                && lesson.subject == counterpart.subject
+                && lesson
+                    .moved
+                    .as_ref()
+                    .is_some_and(|moved| moved.date == back.date)
+        };
+        if vdays[j].2.iter().any(points_back) {
+            continue;
         }
+        counterpart.message = back.to_string();
+        vdays[j].2.push(counterpart);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vertretungscommon::vertretung::moves::MoveKind;
+    use vertretungscommon::vertretung::vertretungsdings::{get_day, Change, TeacherRole};
+
+    /// A substitution page for Monday 19.10.2026 with the rows of its table.
+    fn page(rows: &str) -> String {
+        dated_page("Montag 19.10.2026", rows)
     }
 
-    fn convert_to_compareable(&self) -> (String, String, String, String, String, String, i32) {
-        (
-            self.class.to_string(),
-            self.subject.to_string(),
-            self.room.to_string(),
-            self.teacher.to_string(),
-            self.vtype.to_string(),
-            self.message.to_string(),
-            ((self.time + 1) / 2) as i32,
+    fn dated_page(day: &str, rows: &str) -> String {
+        format!(
+            r#"<html><body>
+<h1 class="list-table-caption">{day}</h1>
+<table class="table">
+<thead><tr><th>Klasse</th><th>Std.</th><th>Fach</th><th>Raum</th><th>Lehrer</th><th>Art</th><th>Mitteilung</th></tr></thead>
+<tbody>{rows}</tbody>
+</table>
+</body></html>"#
         )
     }
 
-    fn to_vec(&self) -> Vec<String> {
-        vec![
-            self.class.to_string(),
-            self.time.to_string(),
-            self.subject.to_string(),
-            self.room.to_string(),
-            self.teacher.to_string(),
-            self.vtype.to_string(),
-            self.message.to_string(),
-        ]
+    fn lessons(rows: &str) -> Vec<Lesson> {
+        let mut last_date = NaiveDate::MIN;
+        get_vday(&page(rows), &mut last_date, &WeekZyklusList::default())
+            .expect("the page has a date")
+            .lessons()
+            .clone()
     }
-}
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct Plan {
-    pub class_name: String,
-    pub days: Vec<PlanDay>,
-    pub subjects: Vec<String>,
-}
+    #[test]
+    fn continuation_row_without_a_row_above_is_skipped() {
+        let lessons = lessons(
+            "<tr><td></td><td>3</td><td></td><td>B12</td><td></td><td>Entfall</td><td></td></tr>
+             <tr><td>TIG21</td><td>5</td><td>Ma</td><td>A1</td><td>MÜL</td><td>Entfall</td><td></td></tr>",
+        );
+        assert_eq!(lessons.len(), 1);
+        assert_eq!(lessons[0].class, "TIG21");
+        assert_eq!(lessons[0].time, 5);
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub enum WeekOption {
-    #[default]
-    None,
-    AandB(PlanLesson),
-    A(PlanLesson),
-    B(PlanLesson),
-    AorB(PlanLesson, PlanLesson),
-}
+    #[test]
+    fn continuation_row_takes_the_empty_cells_from_above() {
+        let lessons = lessons(
+            "<tr><td>TIG21</td><td>2</td><td>Ma</td><td>B12</td><td>MÜL</td><td>Entfall</td><td></td></tr>
+             <tr><td></td><td>3</td><td></td><td>A1</td><td></td><td></td><td></td></tr>",
+        );
+        assert_eq!(lessons.len(), 2);
+        assert_eq!(lessons[1].class, "TIG21");
+        assert_eq!(lessons[1].time, 3);
+        assert_eq!(lessons[1].subject, "Ma");
+        assert_eq!(lessons[1].room, "A1");
+        assert_eq!(lessons[1].teacher, "MÜL");
+        assert!(lessons[1].is_cancelled());
+    }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct PlanDay {
-    pub day: String,
-    pub lessons: [WeekOption; 5],
-}
+    #[test]
+    fn non_breaking_spaces_are_whitespace() {
+        let lessons = lessons(
+            "<tr><td>TIG21&nbsp;</td><td>3</td><td>LK-Ma</td><td>&nbsp;</td><td>MÜL</td>\
+             <td>Vertretung</td><td>Aufgaben&nbsp;&nbsp;im&nbsp;Lernraum</td></tr>",
+        );
+        assert_eq!(lessons[0].class, "TIG21");
+        assert_eq!(lessons[0].room, "");
+        assert_eq!(lessons[0].message, "Aufgaben im Lernraum");
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VDay(String, Zyklus, Vec<Lesson>);
+    #[test]
+    fn entities_are_decoded_and_stay_text() {
+        let lessons = lessons(
+            "<tr><td>TIG21</td><td>3</td><td>Ma</td><td>B12</td><td>MÜL</td>\
+             <td>Vertretung</td><td>Ma &amp; Ph &lt;s&gt;Test&lt;/s&gt;</td></tr>",
+        );
+        assert_eq!(lessons[0].message, "Ma & Ph <s>Test</s>");
+        assert!(lessons[0].struck.is_empty());
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    #[test]
+    fn markup_is_dropped() {
+        let lessons = lessons(
+            "<tr><td><span class=\"klasse\">TIG21</span></td><td><b>3</b></td>\
+             <td><span>Ma</span></td><td>B<span>12</span></td><td><span>MÜL</span></td>\
+             <td>Vertretung</td><td></td></tr>",
+        );
+        assert_eq!(lessons[0].class, "TIG21");
+        assert_eq!(lessons[0].time, 3);
+        assert_eq!(lessons[0].subject, "Ma");
+        assert_eq!(lessons[0].room, "B12");
+        assert_eq!(lessons[0].teacher, "MÜL");
+    }
 
-pub struct Day {
-    pub day: String,
-    pub lessons: [Vec<Lesson>; 10],
-}
+    #[test]
+    fn struck_teacher_and_room_are_kept_apart() {
+        let lessons = lessons(
+            "<tr><td>TIG21</td><td>3</td><td>Ma</td><td><s>A1</s>&nbsp;B12</td>\
+             <td><s>MÜL</s> <span>SCH</span></td><td></td><td></td></tr>",
+        );
+        let lesson = &lessons[0];
+        assert_eq!(lesson.room, "B12");
+        assert_eq!(lesson.struck.room, "A1");
+        assert_eq!(lesson.teacher, "SCH");
+        assert_eq!(lesson.struck.teacher, "MÜL");
+        assert_eq!(lesson.kind, Some(SubstitutionKind::Substitute));
+        assert_eq!(lesson.teacher_role("mül"), Some(TeacherRole::Absent));
+        assert_eq!(lesson.teacher_role("SCH"), Some(TeacherRole::Substitute));
+        assert_eq!(
+            lesson.room_changes(),
+            (vec!["A1".to_string()], vec!["B12".to_string()])
+        );
+    }
 
-impl Day {
-    pub fn new(day: &str) -> Day {
-        Day {
-            day: day.to_string(),
-            lessons: Default::default(),
-        }
+    /// The day of a plan of TIG21 with `Ma` in the periods 1./2. on Monday.
+    fn class_day(rows: &str) -> Day {
+        let plan: Plan = serde_json::from_value(serde_json::json!({
+            "version": 4,
+            "source": "bgy",
+            "class_name": "TIG21",
+            "days": [{
+                "day": "Montag",
+                "lessons": [{
+                    "periods": { "from": 1, "to": 2 },
+                    "lesson": { "AandB": { "subject": "Ma", "room": "A1", "teacher": "MÜL" } },
+                }],
+            }],
+            "subjects": [{ "subject": "Ma" }],
+        }))
+        .unwrap();
+        let mut last_date = NaiveDate::MIN;
+        let vday = get_vday(&page(rows), &mut last_date, &WeekZyklusList::default()).unwrap();
+        get_day(&vday, &plan, &PeriodGrid::default()).unwrap()
+    }
+
+    #[test]
+    fn class_lesson_is_replaced_by_its_first_period_only() {
+        let day = class_day(
+            "<tr><td>TIG21</td><td>1</td><td>Ma</td><td>B12</td><td>MÜL</td><td>Raum</td><td></td></tr>",
+        );
+        assert!(day.slots[0].replaced);
+        assert_eq!(
+            day.slots[0].substitutions[0].changes,
+            vec![Change::Room {
+                from: "A1".to_string(),
+                to: "B12".to_string()
+            }]
+        );
+
+        let day = class_day(
+            "<tr><td>TIG21</td><td>2</td><td>Ma</td><td>B12</td><td>MÜL</td><td>Raum</td><td></td></tr>",
+        );
+        // the lesson is still held in the first period, but changed in the second
+        assert!(!day.slots[0].replaced);
+        assert_eq!(
+            day.slots[0].substitutions[0].changes,
+            vec![Change::Room {
+                from: "A1".to_string(),
+                to: "B12".to_string()
+            }]
+        );
+    }
+
+    fn vday(day: &str, rows: &str) -> VDay {
+        let (mut last_date, weeks) = (NaiveDate::MIN, WeekZyklusList::default());
+        get_vday(&dated_page(day, rows), &mut last_date, &weeks).unwrap()
+    }
+
+    #[test]
+    fn moves_get_the_date_of_the_other_day_and_a_counterpart() {
+        let mut vdays = vec![
+            vday(
+                "Montag 19.10.2026",
+                "<tr><td>TIG21</td><td>5</td><td>Ma</td><td>A1</td><td>MÜL</td>\
+                 <td>Verlegung</td><td>statt Di 5./6.</td></tr>",
+            ),
+            vday(
+                "Dienstag 20.10.2026",
+                "<tr><td>TIG21</td><td>3</td><td>De</td><td>B12</td><td>SCH</td>\
+                 <td>Verlegung</td><td>verlegt von Mo 3. Std</td></tr>",
+            ),
+        ];
+        link_moves(&mut vdays);
+
+        let monday = NaiveDate::from_ymd_opt(2026, 10, 19);
+        let tuesday = NaiveDate::from_ymd_opt(2026, 10, 20);
+        let instead = vdays[0].lessons()[0].moved.as_ref().unwrap();
+        assert_eq!(instead.date, tuesday);
+
+        let from = vdays[1].lessons()[0].moved.as_ref().unwrap();
+        assert_eq!(from.date, monday);
+
+        // both point nowhere back, so each day gets the other end
+        let counterpart = &vdays[1].lessons()[1];
+        assert_eq!((counterpart.time, counterpart.subject.as_str()), (5, "Ma"));
+        let moved = counterpart.moved.as_ref().unwrap();
+        assert_eq!((moved.kind, moved.date), (MoveKind::To, monday));
+        assert_eq!(moved.periods, PeriodRange::single(5));
+
+        let counterpart = &vdays[0].lessons()[1];
+        assert_eq!((counterpart.time, counterpart.subject.as_str()), (3, "De"));
+        let moved = counterpart.moved.as_ref().unwrap();
+        assert_eq!((moved.kind, moved.date), (MoveKind::To, tuesday));
+    }
+
+    #[test]
+    fn weekend_pages_are_skipped_when_linking() {
+        let mut vdays = vec![vday(
+            "Samstag 24.10.2026",
+            "<tr><td>TIG21</td><td>1</td><td>Ma</td><td>A1</td><td>MÜL</td>\
+             <td>Verlegung</td><td>verlegt auf Mo 1.</td></tr>",
+        )];
+        link_moves(&mut vdays);
+        assert_eq!(vdays[0].lessons().len(), 1);
+        assert_eq!(vdays[0].lessons()[0].moved.as_ref().unwrap().date, None);
+    }
+
+    #[test]
+    fn bszet_page_with_notes() {
+        let mut last_date = NaiveDate::MIN;
+        let page = include_str!("fixtures/V_DC_001.html").to_string();
+        let vday = get_vday(&page, &mut last_date, &WeekZyklusList::default()).unwrap();
+        assert_eq!(vday.day(), "Montag 19.10.2026");
+
+        let lessons = vday.lessons();
+        assert_eq!(lessons.len(), 4);
+        assert_eq!((lessons[1].time, lessons[1].room.as_str()), (2, "A101"));
+        assert_eq!(lessons[1].subject, "LK-Ma");
+        assert_eq!(lessons[2].struck.subject, "De");
+        assert_eq!(lessons[2].teacher, "WEB");
+        assert!(lessons[3].is_cancelled());
+
+        let VDay(_, _, _, notes) = &vday;
+        assert_eq!(
+            notes.messages,
+            vec![
+                "Verkürzter Unterricht: Ende nach der 6. Stunde",
+                "Die Sprechstunde von Frau Müller entfällt.",
+            ]
+        );
+        assert_eq!(
+            notes.absent_teachers,
+            vec!["MÜL (1-4)", "SCH", "WEB (5, 6)"]
+        );
+        assert_eq!(notes.absent_classes, vec!["TIG23"]);
+        assert_eq!(notes.blocked_rooms, vec!["B12", "A101"]);
+    }
+
+    #[test]
+    fn page_without_substitutions_keeps_its_notes() {
+        let (mut last_date, weeks) = (NaiveDate::MIN, WeekZyklusList::default());
+        let page = r#"<html><body>
+<h1 class="list-table-caption">Montag 19.10.2026</h1>
+<table><tr><th>Nachrichten zum Tag</th></tr><tr><td>Heute kein Unterricht</td></tr></table>
+<table><tr><td>Seite 1 / 1</td></tr></table>
+</body></html>"#;
+        let vday = get_vday(page, &mut last_date, &weeks).unwrap();
+        assert!(vday.lessons().is_empty());
+        let VDay(_, _, _, notes) = &vday;
+        assert_eq!(notes.messages, vec!["Heute kein Unterricht"]);
     }
 }
@@ -1,10 +1,10 @@
 use reqwest::{Client, Response};
+use serenity::builder::CreateEmbed;
 use serenity::futures::TryFutureExt;
 use serenity::model::id::UserId;
+use serenity::utils::Color;
 use serenity::{prelude::*, CacheAndHttp};
-use sqlx::postgres::PgRow;
-use sqlx::Row;
-use std::env;
+use std::collections::HashMap;
 use std::error::Error;
 use std::sync::Arc;
 use std::time::Duration;
@@ -13,20 +13,34 @@ use tokio::time::sleep;
 use tokio_util::sync::CancellationToken;
 use tracing::{error, info};
 use uuid::Uuid;
+use vertretungscommon::migration::plan_from_value;
+use vertretungscommon::storage::User;
+use vertretungscommon::vertretung::grid::PeriodGrid;
+use vertretungscommon::vertretung::notes::DayNotes;
+use vertretungscommon::vertretung::revocations::Revocation;
+use vertretungscommon::vertretung::vertretungsdings::{get_day, Plan, VDay};
 
-use crate::vertretung::vertretungsdings::{get_day, Plan, VDay};
+use crate::vertretung::revocations::RevocationMessage;
+use crate::vertretung::vertretungsdings::DayMessage;
 
-use crate::DBConnection;
-
-const MIN: std::time::Duration = Duration::from_secs(10);
+use crate::config::ApiConfig;
+use crate::Database;
 
 pub fn init_check_loop(
     arc_http: Arc<CacheAndHttp>,
     arc_data: Arc<RwLock<TypeMap>>,
+    api: Arc<ApiConfig>,
+    interval: Duration,
 ) -> (JoinHandle<()>, CancellationToken) {
     let cancel_token = CancellationToken::new();
     (
-        tokio::spawn(check_loop(arc_http, arc_data, cancel_token.clone())),
+        tokio::spawn(check_loop(
+            arc_http,
+            arc_data,
+            api,
+            interval,
+            cancel_token.clone(),
+        )),
         cancel_token,
     )
 }
@@ -34,49 +48,102 @@ pub fn init_check_loop(
 async fn check_loop(
     arc_http: Arc<CacheAndHttp>,
     arc_data: Arc<RwLock<TypeMap>>,
+    api: Arc<ApiConfig>,
+    interval: Duration,
     cancel_token: CancellationToken,
 ) {
     let client = Client::new();
     let id = Uuid::new_v4().to_string();
-    let base_url = env::var("API_HOST").expect("API_HOST missing in env");
+    let base_url = &api.host;
+    let api_key = &api.key;
     let http = arc_http.as_ref();
+    // the last revocation seen by source, those before the first check
+    // were sent by an earlier run
+    let mut last_revocations: HashMap<String, u64> = HashMap::new();
+    // the days of the last update by source, users are only sent what
+    // changed since then. The first ones seen are only the baseline, an
+    // earlier run sent them already
+    let mut last_vdays: HashMap<String, Vec<VDay>> = HashMap::new();
     loop {
-        let update = client
-            .get(format!("{base_url}/update/{id}"))
+        let sources: Vec<String> = client
+            .get(format!("{base_url}/sources"))
             .send()
             .and_then(Response::json)
             .await
-            .unwrap_or(false);
-
-        info!("update: {update}");
+            .unwrap_or_default();
 
-        if update {
-            let vdays: Vec<VDay> = client
-                .get(format!("{base_url}/vdays"))
+        for source in sources {
+            let update = client
+                .get(format!("{base_url}/sources/{source}/update/{id}"))
+                .bearer_auth(api_key)
                 .send()
                 .and_then(Response::json)
                 .await
-                .unwrap_or_default();
-
-            let connection = {
-                let data_read = arc_data.read().await;
-                match data_read.get::<DBConnection>() {
-                    Some(c) => c.clone(),
-                    _ => continue,
+                .unwrap_or(false);
+
+            info!("update of {source}: {update}");
+
+            if update {
+                // without the days everything would look new next time
+                let vdays: Vec<VDay> = match client
+                    .get(format!("{base_url}/sources/{source}/vdays"))
+                    .send()
+                    .and_then(Response::json)
+                    .await
+                {
+                    Ok(vdays) => vdays,
+                    Err(e) => {
+                        error!("err fetching the vdays of {source}: {:#?}", e);
+                        continue;
+                    }
+                };
+                let previous = last_vdays.insert(source.clone(), vdays.clone());
+                let grid: PeriodGrid = client
+                    .get(format!("{base_url}/sources/{source}/grid"))
+                    .send()
+                    .and_then(Response::json)
+                    .await
+                    .unwrap_or_default();
+                let mut request = client.get(format!("{base_url}/sources/{source}/revocations"));
+                if let Some(last) = last_revocations.get(&source) {
+                    request = request.query(&[("since", last)]);
                 }
-            };
-
-            let query = sqlx::query(
-                "SELECT \"discord_id\", \"embed\", \"data\" FROM \"user\" WHERE \"active\" = true",
-            );
-            let rows = query
-                .fetch_all(connection.as_ref())
-                .await
-                .unwrap_or_default();
-
-            for row in rows {
-                if let Err(e) = read_db_row_and_message(row, http, &vdays).await {
-                    error!("err sendig dm: {:#?}", e);
+                let mut revocations: Vec<Revocation> = request
+                    .send()
+                    .and_then(Response::json)
+                    .await
+                    .unwrap_or_default();
+                let last = revocations.iter().map(|revocation| revocation.id).max();
+                if last_revocations.contains_key(&source) {
+                    if let Some(last) = last {
+                        last_revocations.insert(source.clone(), last);
+                    }
+                } else {
+                    last_revocations.insert(source.clone(), last.unwrap_or_default());
+                    revocations.clear();
+                }
+                let previous = match previous {
+                    Some(previous) => previous,
+                    None => continue,
+                };
+
+                let storage = {
+                    let data_read = arc_data.read().await;
+                    match data_read.get::<Database>() {
+                        Some(s) => s.clone(),
+                        _ => continue,
+                    }
+                };
+
+                let users = storage.users(true).await.unwrap_or_default();
+
+                for user in users {
+                    if let Err(e) =
+                        message_user(user, http, &source, &vdays, &previous, &grid, &revocations)
+                            .await
+                    {
+                        error!("err sendig dm: {:#?}", e);
+                    }
                 }
             }
         }
@@ -84,7 +151,7 @@ async fn check_loop(
         info!("checked for updates");
 
         tokio::select! {
-            _ = sleep(MIN) => {
+            _ = sleep(interval) => {
                 continue;
             }
 
@@ -96,27 +163,63 @@ async fn check_loop(
     }
 }
 
-async fn read_db_row_and_message(
-    row: PgRow,
+async fn message_user(
+    db_user: User,
     http: &CacheAndHttp,
+    source: &str,
     vdays: &Vec<VDay>,
+    previous: &[VDay],
+    grid: &PeriodGrid,
+    revocations: &[Revocation],
 ) -> Result<(), Box<dyn Error>> {
-    let id: i64 = row.try_get(0)?;
-    let embed_activated: bool = row.try_get(1)?;
-    let data = row.try_get(2)?;
+    let embed_activated = db_user.embed;
+    let plan: Plan = plan_from_value(db_user.data)?;
+    if plan.source != source {
+        return Ok(());
+    }
 
-    let user = UserId(id as u64).to_user(http).await?;
+    let user = UserId(db_user.discord_id as u64).to_user(http).await?;
 
-    let plan: Plan = serde_json::from_str(data)?;
+    let revoked: Vec<String> = revocations
+        .iter()
+        .filter(|revocation| revocation.concerns(&plan, previous))
+        .map(Revocation::describe)
+        .collect();
+    if !revoked.is_empty() {
+        user.direct_message(http, |m| {
+            if embed_activated {
+                let mut e = CreateEmbed::default();
+                e.title("Achtung")
+                    .description(revoked.join("\n"))
+                    .color(Color::RED);
+                m.set_embed(e)
+            } else {
+                m.content(revoked.join("\n"))
+            }
+        })
+        .await?;
+    }
 
+    // a day new since the last update is compared to the plan alone
     for vday in vdays {
-        let day = get_day(vday, &plan);
+        let before = match previous.iter().find(|before| before.0 == vday.0) {
+            Some(before) => before.clone(),
+            None => VDay(vday.0.clone(), vday.1, Vec::new(), DayNotes::default()),
+        };
+        // days the plan has no lessons on are left out
+        let day = get_day(vday, &plan, grid)
+            .zip(get_day(&before, &plan, grid))
+            .and_then(|(day, before)| day.changes_since(&before));
+        let day = match day {
+            Some(day) => day,
+            None => continue,
+        };
         user.direct_message(http, |m| {
             if embed_activated {
                 day.to_embed(m);
                 m
             } else {
-                m.content(day.to_string())
+                m.content(day.to_text())
             }
         })
         .await?;
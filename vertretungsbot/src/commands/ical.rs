@@ -0,0 +1,41 @@
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use tracing::{error, info};
+
+use crate::{Api, Database};
+
+#[command]
+pub async fn ical(ctx: &Context, msg: &Message, mut _args: Args) -> CommandResult {
+    let id = msg.author.id.0 as i64;
+    info!("{} used !ical", id);
+
+    let (storage, api) = {
+        let data_read = ctx.data.read().await;
+        (
+            data_read.get::<Database>().unwrap().clone(),
+            data_read.get::<Api>().unwrap().clone(),
+        )
+    };
+
+    let token = storage.ensure_token(id).await?;
+
+    let content = match token {
+        Some(token) => {
+            let base_url = api
+                .public_url
+                .as_ref()
+                .ok_or("api.public_url is not configured")?;
+            format!(
+                "Deine geheimen Links (nicht weitergeben):\nKalender: {base_url}/ical/{token}.ics\nWoche: {base_url}/link/{token}/week"
+            )
+        }
+        None => "Du hast noch keinen Plan hochgeladen, nutze !send_plan".to_string(),
+    };
+
+    if let Err(why) = msg.author.direct_message(ctx, |m| m.content(content)).await {
+        error!("Error sending message: {:?}", why);
+    }
+    Ok(())
+}
@@ -1,4 +1,6 @@
-pub mod send_plan;
 pub mod checker;
+pub mod ical;
+pub mod rooms;
+pub mod send_plan;
+pub mod setter;
 pub mod update;
-pub mod setter;
\ No newline at end of file
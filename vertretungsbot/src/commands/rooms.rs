@@ -0,0 +1,114 @@
+use chrono::NaiveDate;
+use reqwest::Client;
+use serde::Deserialize;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use tracing::{error, info};
+use vertretungscommon::migration::plan_from_value;
+use vertretungscommon::vertretung::vertretungsdings::Plan;
+
+use crate::{Api, Database};
+
+#[derive(Deserialize)]
+struct FreeRooms {
+    date: NaiveDate,
+    period: i64,
+    rooms: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    error: String,
+}
+
+/// `!rooms <period> [dd.mm.yyyy]` lists the free rooms, of the source of
+/// the own plan or the first one of the api.
+#[command]
+pub async fn rooms(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let id = msg.author.id.0 as i64;
+    info!("{} used !rooms", id);
+
+    let period = match args.single::<i64>() {
+        Ok(period) => period,
+        Err(_) => {
+            send_reply(ctx, msg, "Nutzung: !rooms <Stunde> [TT.MM.JJJJ]").await;
+            return Ok(());
+        }
+    };
+    let date = match args.single::<String>() {
+        Ok(date) => match NaiveDate::parse_from_str(&date, "%d.%m.%Y") {
+            Ok(date) => Some(date),
+            Err(_) => {
+                send_reply(ctx, msg, "Das Datum muss als TT.MM.JJJJ angegeben werden").await;
+                return Ok(());
+            }
+        },
+        Err(_) => None,
+    };
+
+    let (storage, api) = {
+        let data_read = ctx.data.read().await;
+        (
+            data_read.get::<Database>().unwrap().clone(),
+            data_read.get::<Api>().unwrap().clone(),
+        )
+    };
+
+    let base_url = &api.host;
+    let client = Client::new();
+    let source = match storage.user(id).await? {
+        Some(user) => plan_from_value::<Plan>(user.data)?.source,
+        None => {
+            let sources: Vec<String> = client
+                .get(format!("{base_url}/sources"))
+                .send()
+                .await?
+                .json()
+                .await?;
+            match sources.into_iter().next() {
+                Some(source) => source,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let mut request = client
+        .get(format!("{base_url}/sources/{source}/rooms/free"))
+        .query(&[("period", period.to_string())]);
+    if let Some(date) = date {
+        request = request.query(&[("date", date.to_string())]);
+    }
+    let res = request.send().await?;
+
+    let content = if res.status().is_success() {
+        let free: FreeRooms = res.json().await?;
+        let date = free.date.format("%d.%m.%Y");
+        match free.rooms.is_empty() {
+            true => format!(
+                "Keine freien Räume in der {}. Stunde am {date}",
+                free.period
+            ),
+            false => format!(
+                "Freie Räume in der {}. Stunde am {date}:\n{}",
+                free.period,
+                free.rooms.join(", ")
+            ),
+        }
+    } else {
+        match res.json::<ApiError>().await {
+            Ok(err) => format!("Fehler: {}", err.error),
+            Err(_) => "Fehler beim Abfragen der Räume".to_string(),
+        }
+    };
+    send_reply(ctx, msg, &content).await;
+
+    Ok(())
+}
+
+async fn send_reply(ctx: &Context, msg: &Message, content: &str) {
+    if let Err(why) = msg.channel_id.say(&ctx.http, content).await {
+        error!("Error sending message: {:?}", why);
+    }
+}
@@ -1,65 +1,79 @@
+use reqwest::{Client, Response};
+use serde::Deserialize;
 use serenity::framework::standard::macros::command;
 use serenity::framework::standard::{Args, CommandResult};
+use serenity::futures::TryFutureExt;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
 use tracing::{error, info};
-use reqwest::Client;
 
+use crate::Api;
 
-use crate::vertretung::vertretungsdings::{Plan};
-
-
-use crate::DBConnection;
-
+#[derive(Deserialize)]
+struct ApiError {
+    error: String,
+    #[serde(default)]
+    details: Vec<String>,
+}
 
 #[command]
-pub async fn send_plan(ctx: &Context, msg: &Message, mut _args: Args) -> CommandResult{
+pub async fn send_plan(ctx: &Context, msg: &Message, mut _args: Args) -> CommandResult {
     let id = msg.author.id.0 as i64;
-    info!("{} used !send_plan", id);  
+    info!("{} used !send_plan", id);
 
-    let connection = {
-        let data_read = ctx.data.read().await;
-        data_read.get::<DBConnection>().unwrap().clone()
-    };  
-    
-
-    let opt_url = msg.attachments.first();
-    if opt_url.is_none() {
-        send_file_error(ctx, msg).await;
-        return Ok(());
-    }
-    let url = &opt_url.unwrap().url;
+    let url = match msg.attachments.first() {
+        Some(attachment) => &attachment.url,
+        None => {
+            send_reply(ctx, msg, "Error with atteched file").await;
+            return Ok(());
+        }
+    };
 
     let client = Client::new();
-    let opt_plan: Option<Plan> = client.get(url)
-    .send().await
-    .unwrap()
-    .json().await 
-    .ok();
+    let opt_plan: Option<serde_json::Value> =
+        client.get(url).send().and_then(Response::json).await.ok();
+
+    let plan = match opt_plan {
+        Some(plan) => plan,
+        None => {
+            send_reply(ctx, msg, "Error with atteched file: no valid json").await;
+            return Ok(());
+        }
+    };
 
+    let api = {
+        let data_read = ctx.data.read().await;
+        data_read.get::<Api>().unwrap().clone()
+    };
+    let res = client
+        .put(format!("{}/plans/{id}", api.host))
+        .bearer_auth(&api.key)
+        .json(&plan)
+        .send()
+        .await?;
 
-    if opt_plan.is_none() {
-        send_file_error(ctx, msg).await;
+    if res.status().is_success() {
+        send_reply(ctx, msg, "Plan gespeichert").await;
         return Ok(());
     }
-    let plan = opt_plan.unwrap();
 
-    let plan_str = serde_json::to_string(&plan).unwrap();
-
-    sqlx::query("INSERT INTO \"user\" VALUES ($1,$2,$3,$4) 
-        ON CONFLICT (discord_id) DO UPDATE SET \"data\" = EXCLUDED.data")
-        .bind(id)
-        .bind(true)
-        .bind(false)
-        .bind(plan_str)
-        .execute(connection.as_ref())
-        .await?;
+    let content = match res.json::<ApiError>().await {
+        Ok(err) => {
+            let mut content = format!("Error with atteched file: {}", err.error);
+            for detail in err.details {
+                content.push_str(&format!("\n- {detail}"));
+            }
+            content
+        }
+        Err(_) => "Error saving the plan".to_string(),
+    };
+    send_reply(ctx, msg, &content).await;
 
     Ok(())
 }
 
-async fn send_file_error(ctx: &Context, msg: &Message){
-    if let Err(why) = msg.channel_id.say(&ctx.http, "Error with atteched file").await {
+async fn send_reply(ctx: &Context, msg: &Message, content: &str) {
+    if let Err(why) = msg.channel_id.say(&ctx.http, content).await {
         error!("Error sending message: {:?}", why);
     }
-}
\ No newline at end of file
+}
@@ -1,51 +1,78 @@
-use reqwest::Client;
+use reqwest::{Client, Response};
 use serenity::framework::standard::macros::command;
 use serenity::framework::standard::{Args, CommandResult};
+use serenity::futures::TryFutureExt;
 use serenity::model::prelude::*;
 use serenity::prelude::*;
-use sqlx::Row;
-use std::env;
 use tracing::{error, info};
+use vertretungscommon::migration::plan_from_value;
+use vertretungscommon::vertretung::grid::PeriodGrid;
+use vertretungscommon::vertretung::vertretungsdings::{get_day, Plan, VDay};
 
-use crate::vertretung::vertretungsdings::{get_day, Plan, VDay};
+use crate::vertretung::vertretungsdings::DayMessage;
 
-use crate::DBConnection;
+use crate::{Api, Database};
 
 #[command]
 pub async fn update(ctx: &Context, msg: &Message, mut _args: Args) -> CommandResult {
     let id = msg.author.id.0 as i64;
     info!("{} used !update", id);
 
-    let connection = {
+    let (storage, api) = {
         let data_read = ctx.data.read().await;
-        data_read.get::<DBConnection>().unwrap().clone()
+        (
+            data_read.get::<Database>().unwrap().clone(),
+            data_read.get::<Api>().unwrap().clone(),
+        )
     };
 
-    let query =
-        sqlx::query("SELECT \"embed\", \"data\" FROM \"user\" WHERE \"discord_id\" = $1").bind(id);
-    let row = query
-        .fetch_one(connection.as_ref())
-        .await
-        .expect("faild query");
+    let user = match storage.user(id).await? {
+        Some(user) => user,
+        None => {
+            if let Err(why) = msg
+                .channel_id
+                .say(
+                    ctx,
+                    "Du hast noch keinen Plan hochgeladen, nutze !send_plan",
+                )
+                .await
+            {
+                error!("Error sending Message: {:?}", why);
+            }
+            return Ok(());
+        }
+    };
 
-    let embed_activated: bool = row.try_get(0).unwrap();
-    let data: String = row.try_get(1).unwrap();
-    let plan: Plan = serde_json::from_str(&data).unwrap();
+    let embed_activated = user.embed;
+    let plan: Plan = match plan_from_value(user.data) {
+        Ok(plan) => plan,
+        Err(err) => {
+            if let Err(why) = msg
+                .channel_id
+                .say(ctx, format!("Dein Plan konnte nicht gelesen werden: {err}"))
+                .await
+            {
+                error!("Error sending Message: {:?}", why);
+            }
+            return Ok(());
+        }
+    };
 
-    let base_url = env::var("API_HOST").unwrap();
+    let base_url = &api.host;
     let client = Client::new();
     let vdays: Vec<VDay> = client
-        .get(format!("{base_url}/vdays"))
+        .get(format!("{base_url}/sources/{}/vdays", plan.source))
         .send()
+        .and_then(Response::json)
+        .await?;
+    let grid: PeriodGrid = client
+        .get(format!("{base_url}/sources/{}/grid", plan.source))
+        .send()
+        .and_then(Response::json)
         .await
-        .unwrap()
-        .json()
-        .await
-        .unwrap();
-
-    for vday in vdays {
-        let day = get_day(&vday, &plan);
+        .unwrap_or_default();
 
+    for day in vdays.iter().filter_map(|vday| get_day(vday, &plan, &grid)) {
         if let Err(why) = msg
             .channel_id
             .send_message(ctx, |m| {
@@ -53,7 +80,7 @@ pub async fn update(ctx: &Context, msg: &Message, mut _args: Args) -> CommandRes
                     day.to_embed(m);
                     m
                 } else {
-                    m.content(day.to_string())
+                    m.content(day.to_text())
                 }
             })
             .await
@@ -64,4 +91,3 @@ pub async fn update(ctx: &Context, msg: &Message, mut _args: Args) -> CommandRes
 
     Ok(())
 }
-
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use vertretungscommon::config::load;
+
+pub use vertretungscommon::config::{ConfigError, DatabaseConfig};
+
+/// Variables starting with this prefix override single settings,
+/// e.g. `VERTRETUNGSBOT__BOT__PREFIX=?` sets `bot.prefix`.
+const ENV_PREFIX: &str = "VERTRETUNGSBOT__";
+
+/// Older variables which still override their setting.
+const ENV_ALIASES: [(&str, &str); 5] = [
+    ("DATABASE_URL", "database.url"),
+    ("DISCORD_TOKEN", "bot.token"),
+    ("API_HOST", "api.host"),
+    ("API_PUBLIC_URL", "api.public_url"),
+    ("API_KEY", "api.key"),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bot: BotConfig,
+    pub api: ApiConfig,
+    pub database: DatabaseConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+    /// token of the discord bot
+    pub token: String,
+    pub prefix: String,
+    /// how often the api is asked for new substitutions
+    pub check_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ApiConfig {
+    /// where the bot reaches the api
+    pub host: String,
+    /// where users reach the api, for the links of !ical
+    pub public_url: Option<String>,
+    /// api key with the scopes updates, plans.read and plans.write
+    pub key: String,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        BotConfig {
+            token: String::new(),
+            prefix: "!".to_string(),
+            check_interval_secs: 10,
+        }
+    }
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        ApiConfig {
+            host: "http://api:8000".to_string(),
+            public_url: None,
+            key: String::new(),
+        }
+    }
+}
+
+impl BotConfig {
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(self.check_interval_secs)
+    }
+}
+
+impl Config {
+    /// Loads the defaults, then the toml file in `CONFIG_FILE` (`config.toml`
+    /// if it exists) and then the overrides from the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let config: Config = load(ENV_PREFIX, &ENV_ALIASES)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+
+        if self.bot.token.is_empty() {
+            errors.push("bot.token is missing".to_string());
+        }
+        if self.bot.prefix.trim().is_empty() {
+            errors.push("bot.prefix must not be empty".to_string());
+        }
+        if self.bot.check_interval_secs == 0 {
+            errors.push("bot.check_interval_secs must be at least 1".to_string());
+        }
+        if self.api.key.is_empty() {
+            errors.push("api.key is missing".to_string());
+        }
+        for (key, url) in [("api.host", &self.api.host)].into_iter().chain(
+            self.api
+                .public_url
+                .iter()
+                .map(|url| ("api.public_url", url)),
+        ) {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(format!("{key} has to be a http(s) url"));
+            }
+        }
+        self.database.validate(&mut errors);
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError(errors)),
+        }
+    }
+}
@@ -1,38 +1,45 @@
 mod commands;
+mod config;
 mod vertretung;
 
 use std::collections::HashSet;
-use std::env;
 use std::sync::Arc;
 
 use commands::checker::init_check_loop;
+use config::{ApiConfig, Config};
 use serenity::async_trait;
 use serenity::client::bridge::gateway::ShardManager;
-use serenity::framework::standard::macros::{group};
+use serenity::framework::standard::macros::group;
 use serenity::framework::StandardFramework;
 use serenity::http::Http;
 use serenity::model::event::ResumedEvent;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
-use tracing::{error, info}; 
-use sqlx::{postgres::{PgPool,PgPoolOptions, PgConnectOptions}};
+use tracing::{error, info};
+use vertretungscommon::storage::{self, Storage};
 
+use crate::commands::ical::*;
+use crate::commands::rooms::*;
 use crate::commands::send_plan::*;
-use crate::commands::update::*;
 use crate::commands::setter::*;
+use crate::commands::update::*;
 pub struct ShardManagerContainer;
 
 impl TypeMapKey for ShardManagerContainer {
     type Value = Arc<Mutex<ShardManager>>;
 }
 
-pub struct DBConnection;
-impl TypeMapKey for DBConnection {
-    type Value = Arc<PgPool>;
+pub struct Database;
+impl TypeMapKey for Database {
+    type Value = Arc<dyn Storage>;
 }
 
-struct Handler;
+pub struct Api;
+impl TypeMapKey for Api {
+    type Value = Arc<ApiConfig>;
+}
 
+struct Handler;
 
 #[async_trait]
 impl EventHandler for Handler {
@@ -46,17 +53,24 @@ impl EventHandler for Handler {
 }
 
 #[group]
-#[commands(send_plan, update, set, embed)]
+#[commands(send_plan, update, set, embed, ical, rooms)]
 struct General;
 
-
 #[tokio::main]
-async fn main() {   
+async fn main() {
     dotenv::dotenv().ok();
 
     tracing_subscriber::fmt::init();
 
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{err}");
+            std::process::exit(1);
+        }
+    };
+
+    let token = config.bot.token.clone();
 
     let http = Http::new(&token);
 
@@ -67,14 +81,13 @@ async fn main() {
             owners.insert(info.owner.id);
 
             (owners, info.id)
-        },
+        }
         Err(why) => panic!("Could not access application info: {:?}", why),
     };
 
     // Create the framework
-    let framework =
-        StandardFramework::new().configure(|c| c.owners(owners)
-        .prefix("!"))
+    let framework = StandardFramework::new()
+        .configure(|c| c.owners(owners).prefix(&config.bot.prefix))
         .group(&GENERAL_GROUP);
 
     let intents = GatewayIntents::GUILD_MESSAGES
@@ -87,33 +100,33 @@ async fn main() {
         .await
         .expect("Err creating client");
 
+    let api = Arc::new(config.api.clone());
     {
         let mut data = client.data.write().await;
         data.insert::<ShardManagerContainer>(client.shard_manager.clone());
-        data.insert::<DBConnection>(Arc::new(
-            PgPoolOptions::new()
-            .max_connections(15)
-            .connect_with(
-                PgConnectOptions::new()
-                .host("db")
-                .database("vertretungsdings")
-                .username("postgres")
-                .password("pass")
-            )
-            .await.expect("error to connect to DB"))
-        );
-    }        
+        let storage = match storage::connect(&config.database).await {
+            Ok(storage) => storage,
+            Err(err) => {
+                error!("connecting to the database: {err}");
+                std::process::exit(1);
+            }
+        };
+        data.insert::<Database>(storage);
+        data.insert::<Api>(Arc::clone(&api));
+    }
     let shard_manager = client.shard_manager.clone();
 
-    
-
     let (loop_handle, loop_stop) = init_check_loop(
         Arc::clone(&client.cache_and_http),
-        Arc::clone(&client.data)
+        Arc::clone(&client.data),
+        api,
+        config.bot.check_interval(),
     );
 
-    tokio::spawn(async move {   
-        tokio::signal::ctrl_c().await.expect("Could not register ctrl+c handler");  
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Could not register ctrl+c handler");
         shard_manager.lock().await.shutdown_all().await;
         loop_stop.cancel();
         loop_handle.await.expect("couldn't stop loop nice");
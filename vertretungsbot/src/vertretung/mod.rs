@@ -1 +1,3 @@
-pub mod vertretungsdings;
\ No newline at end of file
+pub mod notes;
+pub mod revocations;
+pub mod vertretungsdings;
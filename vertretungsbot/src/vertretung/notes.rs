@@ -0,0 +1,44 @@
+use serenity::builder::CreateEmbed;
+use serenity::utils::Color;
+use vertretungscommon::vertretung::notes::DayNotes;
+
+/// The notes of a day as they are sent to discord.
+pub trait NotesMessage {
+    fn to_embed(&self) -> CreateEmbed;
+
+    /// Lines under a "Nachrichten zum Tag" heading, the lists joined by
+    /// commas.
+    fn to_text(&self) -> String;
+}
+
+fn lists(notes: &DayNotes) -> impl Iterator<Item = (&str, &Vec<String>)> {
+    [
+        ("Abwesende Lehrer", &notes.absent_teachers),
+        ("Abwesende Klassen", &notes.absent_classes),
+        ("Blockierte Räume", &notes.blocked_rooms),
+    ]
+    .into_iter()
+    .filter(|(_, list)| !list.is_empty())
+}
+
+impl NotesMessage for DayNotes {
+    fn to_embed(&self) -> CreateEmbed {
+        let mut e = CreateEmbed::default();
+        e.title("Nachrichten zum Tag").color(Color::BLUE);
+        if !self.messages.is_empty() {
+            e.description(self.messages.join("\n"));
+        }
+        e.fields(lists(self).map(|(label, list)| (label, list.join(", "), false)));
+        e
+    }
+
+    fn to_text(&self) -> String {
+        let lines: Vec<String> = self
+            .messages
+            .iter()
+            .cloned()
+            .chain(lists(self).map(|(label, list)| format!("{label}: {}", list.join(", "))))
+            .collect();
+        format!("Nachrichten zum Tag\n{}", lines.join("\n"))
+    }
+}
@@ -0,0 +1,23 @@
+use vertretungscommon::vertretung::revocations::Revocation;
+
+/// A revocation as it's sent to discord.
+pub trait RevocationMessage {
+    /// A line like `Achtung: Ausfall aufgehoben, Montag 19.10.2026 3. Ma`.
+    fn describe(&self) -> String;
+}
+
+impl RevocationMessage for Revocation {
+    fn describe(&self) -> String {
+        match &self.lesson {
+            Some(lesson) if lesson.is_cancelled() => format!(
+                "Achtung: Ausfall aufgehoben, {} {}. {}",
+                self.day, lesson.time, lesson.subject
+            ),
+            Some(lesson) => format!(
+                "Achtung: Vertretung zurückgenommen, {} {}. {}",
+                self.day, lesson.time, lesson.subject
+            ),
+            None => format!("Achtung: Vertretungsplan für {} zurückgezogen", self.day),
+        }
+    }
+}
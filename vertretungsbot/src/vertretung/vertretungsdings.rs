@@ -1,135 +1,91 @@
 use prettytable::*;
-use serde::{Deserialize, Serialize};
 use serenity::builder::{CreateEmbed, CreateMessage};
 use serenity::utils::Color;
-
-#[derive(Default, Serialize, Deserialize, Debug, Clone, Copy)]
-pub enum Zyklus {
-    #[default]
-    I,
-    II,
+use vertretungscommon::vertretung::kinds::SubstitutionKind;
+use vertretungscommon::vertretung::notes::DayNotes;
+use vertretungscommon::vertretung::vertretungsdings::{Change, Day, Lesson, Slot};
+
+use super::notes::NotesMessage;
+
+/// A cell as shown, with the struck out original value crossed out.
+fn with_struck(struck: &str, current: &str) -> String {
+    match (struck.is_empty(), current.is_empty()) {
+        (true, _) => current.to_string(),
+        (false, true) => format!("~~{struck}~~"),
+        (false, false) => format!("~~{struck}~~ {current}"),
+    }
 }
 
-pub fn get_day(VDay(day_str, zyklus, v_lessons): &VDay, plan: &Plan) -> Day {
-    let mut splits = day_str.split_whitespace().into_iter();
-    let day_name = splits.next().unwrap();
+/// A lesson as it's sent to discord, as an embed or a row of a table.
+trait LessonMessage {
+    /// The changes as lines like `Ma: B13 → A06`.
+    fn describe_changes(&self) -> String;
 
-    let mut res_day: Day = Day::new(&day_str.as_str());
-
-    for v_lesson in v_lessons.iter().filter(|item| {
-        item.class.contains(&plan.class_name) && is_in(&item.subject, &plan.subjects)
-    }) {
-        res_day
-            .lessons
-            .get_mut((v_lesson.time - 1) as usize)
-            .unwrap()
-            .push(v_lesson.clone());
-    }
-
-    let default_plan_day = PlanDay::default();
-    let plan_day = plan
-        .days
-        .iter()
-        .find(|item| item.day.contains(day_name))
-        .unwrap_or(&default_plan_day);
-    let empty_times = res_day
-        .lessons
-        .iter_mut()
-        .enumerate()
-        .filter(|(i, item)| i % 2 == 0 && item.len() == 0);
-
-    for (i, ls) in empty_times {
-        let normal = plan_day.lessons.get(i / 2).unwrap_or(&WeekOption::None);
-        match normal {
-            WeekOption::AandB(l) => ls.push(l.to_lesson()),
-            WeekOption::A(l) => match zyklus {
-                Zyklus::I => ls.push(l.to_lesson()),
-                Zyklus::II => {}
-            },
-            WeekOption::B(l) => match zyklus {
-                Zyklus::II => ls.push(l.to_lesson()),
-                Zyklus::I => {}
-            },
-            WeekOption::AorB(l1, l2) => match zyklus {
-                Zyklus::I => ls.push(l1.to_lesson()),
-                Zyklus::II => ls.push(l2.to_lesson()),
-            },
-            WeekOption::None => (),
-        }
-    }
-    return res_day;
-}
+    /// The substitution as it's shown once it no longer applies.
+    fn withdrawn(&self) -> Lesson;
 
-fn is_in(string: &str, vec: &Vec<String>) -> bool {
-    for s in vec {
-        if string.contains(s) {
-            return true;
-        }
-    }
-    return false;
-}
+    fn to_embed(&self) -> CreateEmbed;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PlanLesson {
-    time: i64,
-    subject: String,
-    room: String,
-    teacher: String,
+    fn to_row(&self) -> Row;
 }
 
-impl PlanLesson {
-    pub fn to_lesson(&self) -> Lesson {
-        Lesson::new(
-            self.time,
-            self.subject.as_str(),
-            self.room.as_str(),
-            self.teacher.as_str(),
-        )
+impl LessonMessage for Lesson {
+    fn describe_changes(&self) -> String {
+        let subject = &self.subject;
+        self.changes
+            .iter()
+            .map(|change| match change {
+                Change::Room { from, to } => format!("{subject}: Raum {from} → {to}"),
+                Change::Teacher { from, to } => format!("{subject}: {from} → {to}"),
+                Change::Cancelled => format!("{subject} entfällt"),
+                Change::Added => format!("{subject} zusätzlich"),
+                Change::Moved(moved) => format!("{subject} {moved}"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Lesson {
-    pub class: String,
-    pub time: i64,
-    pub subject: String,
-    pub room: String,
-    pub teacher: String,
-    pub vtype: String,
-    pub message: String,
-}
 
-impl Lesson {
-    fn new(time: i64, subject: &str, room: &str, teacher: &str) -> Lesson {
+    fn withdrawn(&self) -> Lesson {
         Lesson {
-            class: String::new(),
-            time,
-            subject: subject.to_string(),
-            room: room.to_string(),
-            teacher: teacher.to_string(),
-            vtype: String::new(),
-            message: String::new(),
+            vtype: "zurückgenommen".to_string(),
+            kind: None,
+            changes: Vec::new(),
+            ..self.clone()
         }
     }
+
     fn to_embed(&self) -> CreateEmbed {
         let timestr = format!("{}.", self.time);
         let emptystring = String::from(" ");
+        let subject = with_struck(&self.struck.subject, &self.subject);
+        let room = with_struck(&self.struck.room, &self.room);
+        let teacher = with_struck(&self.struck.teacher, &self.teacher);
         let fields = vec![
             (timestr.as_str(), &emptystring, false),
-            ("Fach", &self.subject, true),
-            ("Raum", &self.room, true),
-            ("Lehrer", &self.teacher, true),
+            ("Klasse", &self.class, true),
+            ("Fach", &subject, true),
+            ("Raum", &room, true),
+            ("Lehrer", &teacher, true),
             ("Art", &self.vtype, true),
             ("Mitteilung", &self.message, true),
         ]
         .into_iter()
-        .filter(|(_, s, _)| s.len() > 0);
+        .filter(|(_, s, _)| !s.is_empty());
 
         let mut e = CreateEmbed::default();
         e.fields(fields);
+        if !self.changes.is_empty() {
+            e.description(self.describe_changes());
+        }
 
-        if self.vtype.len() > 0 || self.message.len() > 0 {
-            e.color(Color::RED);
+        match self.kind {
+            Some(SubstitutionKind::Cancellation) => {
+                e.color(Color::RED);
+            }
+            Some(_) => {
+                e.color(Color::ORANGE);
+            }
+            None => {}
         }
         e
     }
@@ -137,87 +93,71 @@ impl Lesson {
     fn to_row(&self) -> Row {
         row![
             self.time.to_string(),
-            self.subject,
-            self.room,
-            self.teacher,
+            self.class,
+            with_struck(&self.struck.subject, &self.subject),
+            with_struck(&self.struck.room, &self.room),
+            with_struck(&self.struck.teacher, &self.teacher),
             self.vtype,
             self.message
         ]
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct Plan {
-    pub class_name: String,
-    pub days: Vec<PlanDay>,
-    pub subjects: Vec<String>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub enum WeekOption {
-    #[default]
-    None,
-    AandB(PlanLesson),
-    A(PlanLesson),
-    B(PlanLesson),
-    AorB(PlanLesson, PlanLesson),
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
-pub struct PlanDay {
-    pub day: String,
-    pub lessons: [WeekOption; 5],
-}
+/// A day as it's sent to discord, as embeds or as a table in a code block.
+pub trait DayMessage {
+    fn to_table(&self) -> Table;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VDay(pub String, pub Zyklus, pub Vec<Lesson>);
+    fn to_embed(&self, m: &mut CreateMessage);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Day {
-    pub day: String,
-    pub lessons: [Vec<Lesson>; 10],
+    fn to_text(&self) -> String;
 }
 
-impl Day {
-    pub fn new(day: &str) -> Day {
-        Day {
-            day: day.to_string(),
-            lessons: Default::default(),
-        }
-    }
-
-    pub fn to_table(&self) -> Table {
+impl DayMessage for Day {
+    fn to_table(&self) -> Table {
         let mut table = Table::new();
         table.set_titles(row![
             "Stunde",
+            "Klasse",
             "Fach",
             "Raum",
             "Lehrer",
             "Type",
             "Mitteilung"
         ]);
-        self.lessons
-            .iter()
-            .filter(|item| item.len() > 0)
-            .for_each(|lesson| {
-                for l in lesson {
-                    table.add_row(l.to_row());
-                }
-            });
+        self.slots.iter().flat_map(Slot::lessons).for_each(|l| {
+            table.add_row(l.to_row());
+        });
+        self.removed.iter().map(Lesson::withdrawn).for_each(|l| {
+            table.add_row(l.to_row());
+        });
         table
     }
 
-    pub fn to_embed(&self, m: &mut CreateMessage) {
-        let embeds: Vec<CreateEmbed> = self
-            .lessons
-            .iter()
-            .map(|ls| ls.iter().map(|l| l.to_embed()))
-            .flatten()
+    fn to_embed(&self, m: &mut CreateMessage) {
+        let notes = Some(&self.notes).filter(|notes| !notes.is_empty());
+        let embeds: Vec<CreateEmbed> = notes
+            .map(DayNotes::to_embed)
+            .into_iter()
+            .chain(
+                self.slots
+                    .iter()
+                    .flat_map(Slot::lessons)
+                    .map(LessonMessage::to_embed),
+            )
+            .chain(self.removed.iter().map(|l| l.withdrawn().to_embed()))
             .collect();
         m.content(&self.day).set_embeds(embeds);
     }
 
-    pub fn to_string(&self) -> String {
-        format! {"```{}\n{}```",self.day, self.to_table().to_string()}
+    fn to_text(&self) -> String {
+        match self.notes.is_empty() {
+            true => format!("```{}\n{}```", self.day, self.to_table()),
+            false => format!(
+                "```{}\n{}\n\n{}```",
+                self.day,
+                self.notes.to_text(),
+                self.to_table()
+            ),
+        }
     }
 }
@@ -0,0 +1,141 @@
+/// A class with an optional group, e.g. `TIG21/1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassRef {
+    pub class: String,
+    pub group: Option<String>,
+}
+
+/// The classes of a substitution, parsed from cells like `TIG21, TIG22`,
+/// `TIG21-23` or `TIG21/1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassSet(pub Vec<ClassRef>);
+
+impl ClassSet {
+    pub fn parse(cell: &str) -> ClassSet {
+        let mut classes = Vec::new();
+        for token in cell
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+        {
+            let (class, group) = match token.split_once('/') {
+                Some((class, group)) if !group.is_empty() => (class, Some(group.to_uppercase())),
+                Some((class, _)) => (class, None),
+                None => (token, None),
+            };
+            for class in expand_range(&class.to_uppercase()) {
+                classes.push(ClassRef {
+                    class,
+                    group: group.clone(),
+                });
+            }
+        }
+        ClassSet(classes)
+    }
+
+    /// Whether a student of `class` (and `group`) is affected.
+    ///
+    /// Classes are compared case-insensitively and exactly, unless `class`
+    /// ends with `*`, which matches every class starting with the rest.
+    /// An entry without a group applies to all groups of its class, one with
+    /// a group only to that group, or to a plan which didn't name a group.
+    pub fn matches(&self, class: &str, group: Option<&str>) -> bool {
+        let class = class.trim().to_uppercase();
+        let group = group.map(str::to_uppercase);
+        self.0.iter().any(|entry| {
+            let class_matches = match class.strip_suffix('*') {
+                Some(prefix) => entry.class.starts_with(prefix),
+                None => entry.class == class,
+            };
+            let group_matches = match (&entry.group, &group) {
+                (Some(entry_group), Some(group)) => entry_group == group,
+                _ => true,
+            };
+            class_matches && group_matches
+        })
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|entry| entry.class.as_str())
+    }
+}
+
+/// `TIG21-23` and `TIG21-TIG23` become `TIG21`, `TIG22` and `TIG23`,
+/// anything else is kept as it is.
+fn expand_range(class: &str) -> Vec<String> {
+    let (first, last) = match class.split_once('-') {
+        Some(range) => range,
+        None => return vec![class.to_string()],
+    };
+    let digits_at = first.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let (prefix, start) = first.split_at(digits_at);
+    // the end can repeat the prefix or only give the last digits
+    let end = last.strip_prefix(prefix).unwrap_or(last);
+    if start.is_empty() || end.is_empty() || !end.chars().all(|c| c.is_ascii_digit()) {
+        return vec![class.to_string()];
+    }
+    let end = match end.len() < start.len() {
+        true => format!("{}{end}", &start[..start.len() - end.len()]),
+        false => end.to_string(),
+    };
+    match (start.parse::<u32>(), end.parse::<u32>()) {
+        (Ok(from), Ok(to)) if from <= to && to - from < 100 => (from..=to)
+            .map(|number| format!("{prefix}{number:0width$}", width = start.len()))
+            .collect(),
+        _ => vec![class.to_string()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classes(cell: &str) -> Vec<String> {
+        ClassSet::parse(cell)
+            .classes()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn ranges_are_expanded() {
+        assert_eq!(classes("TIG21-23"), ["TIG21", "TIG22", "TIG23"]);
+        assert_eq!(classes("TIG21-TIG23"), ["TIG21", "TIG22", "TIG23"]);
+        assert_eq!(classes("TIG09-11"), ["TIG09", "TIG10", "TIG11"]);
+        // a range backwards or without digits stays as it is
+        assert_eq!(classes("TIG23-21"), ["TIG23-21"]);
+        assert_eq!(classes("TIG-ABC"), ["TIG-ABC"]);
+    }
+
+    #[test]
+    fn lists_are_split() {
+        assert_eq!(classes("tig21, TIG22;TIG23"), ["TIG21", "TIG22", "TIG23"]);
+    }
+
+    #[test]
+    fn groups_apply_to_their_group() {
+        let set = ClassSet::parse("TIG21/1");
+        assert_eq!(
+            set.0,
+            [ClassRef {
+                class: "TIG21".to_string(),
+                group: Some("1".to_string()),
+            }]
+        );
+        assert!(set.matches("TIG21", Some("1")));
+        assert!(!set.matches("TIG21", Some("2")));
+        // a plan without a group gets the substitutions of all groups
+        assert!(set.matches("TIG21", None));
+
+        let set = ClassSet::parse("TIG21");
+        assert!(set.matches("TIG21", Some("2")));
+    }
+
+    #[test]
+    fn wildcards_match_by_prefix() {
+        assert!(ClassSet::parse("TIG22").matches("TIG2*", None));
+        assert!(ClassSet::parse("TIG21-23").matches("tig2*", Some("1")));
+        assert!(!ClassSet::parse("TIG31").matches("TIG2*", None));
+        // without the wildcard the class has to match exactly
+        assert!(!ClassSet::parse("TIG22").matches("TIG2", None));
+    }
+}
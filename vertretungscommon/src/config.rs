@@ -0,0 +1,137 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    env,
+    fmt::{Display, Formatter},
+    fs,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// `postgres://...` or `sqlite:...`, the other fields are ignored when set
+    pub url: Option<String>,
+    pub host: String,
+    pub name: String,
+    pub user: String,
+    pub password: String,
+    pub max_connections: u32,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: None,
+            host: "db".to_string(),
+            name: "vertretungsdings".to_string(),
+            user: "postgres".to_string(),
+            password: "pass".to_string(),
+            max_connections: 15,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn validate(&self, errors: &mut Vec<String>) {
+        if let Some(url) = &self.url {
+            if !["postgres:", "postgresql:", "sqlite:"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme))
+            {
+                errors.push("database.url has to start with postgres: or sqlite:".to_string());
+            }
+        }
+        if self.max_connections == 0 {
+            errors.push("database.max_connections must be at least 1".to_string());
+        }
+    }
+}
+
+/// All problems found while loading the configuration.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {error}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Loads the defaults, then the toml file in `CONFIG_FILE` (`config.toml`
+/// if it exists) and then the overrides from the environment. Variables
+/// starting with `env_prefix` override single settings, e.g.
+/// `{env_prefix}SERVER__PORT=8080` sets `server.port`, `aliases` are older
+/// variables which still override their setting.
+pub fn load<T>(env_prefix: &str, aliases: &[(&str, &str)]) -> Result<T, ConfigError>
+where
+    T: Default + Serialize + DeserializeOwned,
+{
+    let mut value = serde_json::to_value(T::default()).expect("default config is valid json");
+
+    let (path, required) = match env::var("CONFIG_FILE") {
+        Ok(path) => (path, true),
+        Err(_) => ("config.toml".to_string(), false),
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => {
+            let file: Value =
+                toml::from_str(&text).map_err(|err| ConfigError(vec![format!("{path}: {err}")]))?;
+            merge(&mut value, file);
+        }
+        Err(err) if required => return Err(ConfigError(vec![format!("{path}: {err}")])),
+        Err(_) => {}
+    }
+
+    let mut overrides: Vec<(String, String)> = aliases
+        .iter()
+        .filter_map(|(var, key)| Some((key.to_string(), env::var(var).ok()?)))
+        .collect();
+    overrides.extend(env::vars().filter_map(|(var, val)| {
+        let key = var.strip_prefix(env_prefix)?;
+        Some((key.to_lowercase().replace("__", "."), val))
+    }));
+    for (key, val) in overrides {
+        set_env_override(&mut value, &key, val)?;
+    }
+
+    serde_json::from_value(value).map_err(|err| ConfigError(vec![err.to_string()]))
+}
+
+/// Recursively merges the tables of `other` into `target`.
+fn merge(target: &mut Value, other: Value) {
+    match (target, other) {
+        (Value::Object(target), Value::Object(other)) => {
+            for (key, value) in other {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, other) => *target = other,
+    }
+}
+
+/// Sets the setting at the dotted `key`. The value is parsed like the
+/// current one, strings and unset options take it as it is.
+fn set_env_override(value: &mut Value, key: &str, val: String) -> Result<(), ConfigError> {
+    let mut target = value;
+    for part in key.split('.') {
+        if target.is_null() {
+            *target = Value::Object(Map::new());
+        }
+        target = match target {
+            Value::Object(map) => map.entry(part).or_insert(Value::Null),
+            _ => return Err(ConfigError(vec![format!("unknown setting {key}")])),
+        };
+    }
+    *target = match target {
+        Value::String(_) | Value::Null => Value::String(val),
+        _ => serde_json::from_str(&val)
+            .map_err(|_| ConfigError(vec![format!("{key}: can't parse {val:?}")]))?,
+    };
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// How the subject of a [`CourseSelector`] is compared.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// the whole course or its subject without the level, case-insensitively
+    #[default]
+    Exact,
+    /// courses or subjects starting with it, case-insensitively
+    Prefix,
+    /// a regular expression searched in the subject and in the struck out one
+    Regex,
+}
+
+/// A course of a class plan, e.g. `{"subject": "Ma", "level": "LK"}` for
+/// the substitutions of `LK-Ma` but not those of `GK-Ma`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(from = "StoredCourse")]
+pub struct CourseSelector {
+    pub subject: String,
+    pub mode: MatchMode,
+    /// only substitutions this teacher is part of, struck out or not
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teacher: Option<String>,
+    /// course level in front of the subject, like `LK` in `LK-Ma`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// `subject` of the regex mode compiled when the plan is loaded, `None`
+    /// if it's no valid regex
+    #[serde(skip)]
+    compiled: Option<Regex>,
+}
+
+/// A [`CourseSelector`] as it is stored in a plan.
+#[derive(Deserialize)]
+struct StoredCourse {
+    subject: String,
+    #[serde(default)]
+    mode: MatchMode,
+    #[serde(default)]
+    teacher: Option<String>,
+    #[serde(default)]
+    level: Option<String>,
+}
+
+impl From<StoredCourse> for CourseSelector {
+    fn from(course: StoredCourse) -> Self {
+        let compiled = match course.mode {
+            MatchMode::Regex => Regex::new(&course.subject).ok(),
+            _ => None,
+        };
+        CourseSelector {
+            subject: course.subject,
+            mode: course.mode,
+            teacher: course.teacher,
+            level: course.level,
+            compiled,
+        }
+    }
+}
+
+impl CourseSelector {
+    pub fn regex(&self) -> Result<Regex, regex::Error> {
+        Regex::new(&self.subject)
+    }
+
+    /// Whether a substitution with the subjects `subjects`, the current and
+    /// the struck out one, is one of this course. `has_teacher` tells if a
+    /// teacher is part of it.
+    pub fn matches(&self, subjects: &[&str], has_teacher: impl FnOnce(&str) -> bool) -> bool {
+        let subjects: Vec<&str> = subjects
+            .iter()
+            .copied()
+            .filter(|text| !text.is_empty())
+            .collect();
+        let mut courses = subjects.iter().flat_map(|text| courses(text));
+        let subject = self.subject.trim().to_lowercase();
+        let course_matches = match self.mode {
+            MatchMode::Regex => {
+                self.compiled
+                    .as_ref()
+                    .is_some_and(|re| subjects.iter().any(|text| re.is_match(text)))
+                    && courses.any(|course| self.level_matches(&course))
+            }
+            MatchMode::Exact => courses.any(|course| {
+                self.level_matches(&course) && (course.name == subject || course.subject == subject)
+            }),
+            MatchMode::Prefix => courses.any(|course| {
+                self.level_matches(&course)
+                    && (course.name.starts_with(&subject) || course.subject.starts_with(&subject))
+            }),
+        };
+        course_matches && self.teacher.as_deref().is_none_or(has_teacher)
+    }
+
+    fn level_matches(&self, course: &Course) -> bool {
+        match &self.level {
+            Some(level) => course.level.as_deref() == Some(level.trim().to_lowercase().as_str()),
+            None => true,
+        }
+    }
+}
+
+/// A course of a subject cell, all lowercase. `LK-Ma` has the level `lk`
+/// and the subject `ma`, without a `-` the subject is the whole name.
+struct Course {
+    name: String,
+    level: Option<String>,
+    subject: String,
+}
+
+/// The courses of the text of a subject cell, like `LK-Ma, GK-Ph`.
+fn courses(text: &str) -> Vec<Course> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let name = name.to_lowercase();
+            let (level, subject) = match name.split_once('-') {
+                Some((level, subject)) if !level.is_empty() && !subject.is_empty() => {
+                    (Some(level.to_string()), subject.to_string())
+                }
+                _ => (None, name.clone()),
+            };
+            Course {
+                name,
+                level,
+                subject,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn selector(value: serde_json::Value) -> CourseSelector {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn regex_matches_the_current_and_the_struck_out_subject() {
+        let course = selector(json!({ "subject": "^Ma$", "mode": "regex" }));
+        assert!(course.matches(&["Ma", "De"], |_| true));
+        assert!(course.matches(&["De", "Ma"], |_| true));
+        assert!(!course.matches(&["De", ""], |_| true));
+    }
+
+    #[test]
+    fn invalid_regex_matches_nothing() {
+        let course = selector(json!({ "subject": "(Ma", "mode": "regex" }));
+        assert!(course.regex().is_err());
+        assert!(!course.matches(&["(Ma", ""], |_| true));
+    }
+
+    #[test]
+    fn exact_and_prefix_compare_courses() {
+        let course = selector(json!({ "subject": "ma", "level": "LK" }));
+        assert!(course.matches(&["LK-Ma, GK-Ph", ""], |_| true));
+        assert!(!course.matches(&["GK-Ma", ""], |_| true));
+
+        let course = selector(json!({ "subject": "Ph", "mode": "prefix" }));
+        assert!(course.matches(&["Phy", ""], |_| true));
+        assert!(!course.matches(&["Ch", ""], |_| true));
+    }
+
+    #[test]
+    fn teacher_has_to_be_part_of_it() {
+        let course = selector(json!({ "subject": "Ma", "teacher": "MÜL" }));
+        assert!(course.matches(&["Ma", ""], |teacher| teacher == "MÜL"));
+        assert!(!course.matches(&["Ma", ""], |_| false));
+    }
+
+    #[test]
+    fn stored_without_the_compiled_regex() {
+        let stored = json!({ "subject": "^Ma$", "mode": "regex" });
+        assert_eq!(
+            serde_json::to_value(selector(stored.clone())).unwrap(),
+            stored
+        );
+    }
+}
@@ -0,0 +1,9 @@
+//! What the api and the bot share: the storage of the users and their plans,
+//! the plan format with its migrations, the substitutions with their merge
+//! into a plan and the loading of the configuration.
+pub mod classes;
+pub mod config;
+pub mod courses;
+pub mod migration;
+pub mod storage;
+pub mod vertretung;
@@ -0,0 +1,194 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Version of the plan format written by this build.
+pub const CURRENT_VERSION: u64 = 6;
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[n]` upgrades a plan of version `n` to version `n + 1`.
+const MIGRATIONS: [Migration; CURRENT_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6];
+
+/// Plans before versioning had no `version` field.
+fn v0_to_v1(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(1));
+    Ok(value)
+}
+
+/// Plans before sources all belonged to the BGy pages.
+fn v1_to_v2(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(2));
+    root.entry("source").or_insert(Value::from("bgy"));
+    Ok(value)
+}
+
+/// Plans before teacher plans were all class plans.
+fn v2_to_v3(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(3));
+    root.entry("kind").or_insert(Value::from("class"));
+    Ok(value)
+}
+
+/// Classes are matched exactly since groups exist, `TIG21/1` is split up.
+/// `TIG21/` names no group.
+fn v3_to_v4(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(4));
+    let class_name = root
+        .get("class_name")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if let Some((class, group)) = class_name.split_once('/') {
+        let (class, group) = (class.trim().to_string(), group.trim().to_string());
+        root.insert("class_name".to_string(), Value::from(class));
+        if !group.is_empty() {
+            root.insert("group".to_string(), Value::from(group));
+        }
+    }
+    Ok(value)
+}
+
+/// Subjects were plain strings, they become courses matched exactly so
+/// that `Ma` no longer matches `Mathe-Ü` or `LK-Ma2`.
+fn v4_to_v5(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(5));
+    if let Some(Value::Array(subjects)) = root.get_mut("subjects") {
+        for subject in subjects.iter_mut() {
+            if let Value::String(s) = subject {
+                *subject = serde_json::json!({ "subject": s, "mode": "exact" });
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Days had exactly 5 double periods, each lesson repeating its first
+/// period in `time`. Now only the lessons are listed with their periods.
+fn v5_to_v6(mut value: Value) -> Result<Value, String> {
+    let root = value.as_object_mut().ok_or("plan has to be an object")?;
+    root.insert("version".to_string(), Value::from(6));
+    let days = match root.get_mut("days").and_then(Value::as_array_mut) {
+        Some(days) => days,
+        None => return Ok(value),
+    };
+    for day in days {
+        let lessons = match day.get_mut("lessons") {
+            // plans without a version can already list their periods
+            Some(Value::Array(lessons)) if !lessons.iter().any(|l| l.get("periods").is_some()) => {
+                std::mem::take(lessons)
+            }
+            _ => continue,
+        };
+        let blocks = lessons
+            .into_iter()
+            .enumerate()
+            .filter(|(_, option)| option != "None")
+            .map(|(slot, mut option)| {
+                for lesson in option
+                    .as_object_mut()
+                    .into_iter()
+                    .flat_map(|o| o.values_mut())
+                {
+                    match lesson {
+                        Value::Array(pair) => pair.iter_mut().for_each(remove_time),
+                        lesson => remove_time(lesson),
+                    }
+                }
+                let first = slot as i64 * 2 + 1;
+                serde_json::json!({
+                    "periods": { "from": first, "to": first + 1 },
+                    "lesson": option,
+                })
+            })
+            .collect();
+        day["lessons"] = Value::Array(blocks);
+    }
+    Ok(value)
+}
+
+fn remove_time(lesson: &mut Value) {
+    if let Some(lesson) = lesson.as_object_mut() {
+        lesson.remove("time");
+    }
+}
+
+pub fn plan_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Upgrades a plan of any known version to the current format.
+pub fn migrate_plan_value(mut value: Value) -> Result<Value, String> {
+    let version = plan_version(&value);
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "plan version {version} is newer than the supported version {CURRENT_VERSION}"
+        ));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+    Ok(value)
+}
+
+/// Why a stored plan can't be read.
+#[derive(Debug)]
+pub enum MigrationError {
+    /// the plan can't be upgraded to the current version
+    Upgrade(String),
+    /// the upgraded plan doesn't fit the current format
+    Format(serde_json::Error),
+}
+
+impl Display for MigrationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MigrationError::Upgrade(err) => write!(f, "{err}"),
+            MigrationError::Format(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for MigrationError {}
+
+/// The plan stored as `value`, upgraded to the current format first.
+pub fn plan_from_value<Plan: DeserializeOwned>(value: Value) -> Result<Plan, MigrationError> {
+    let value = migrate_plan_value(value).map_err(MigrationError::Upgrade)?;
+    serde_json::from_value(value).map_err(MigrationError::Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn class_groups_are_split_off() {
+        let plan = migrate_plan_value(json!({ "version": 3, "class_name": "TIG21/1" })).unwrap();
+        assert_eq!(plan["class_name"], "TIG21");
+        assert_eq!(plan["group"], "1");
+
+        let plan = migrate_plan_value(json!({ "version": 3, "class_name": "TIG21/" })).unwrap();
+        assert_eq!(plan["class_name"], "TIG21");
+        assert!(plan.get("group").is_none());
+    }
+
+    #[test]
+    fn plain_subjects_are_matched_exactly() {
+        let plan = migrate_plan_value(json!({ "version": 4, "subjects": ["Ma", "C++"] })).unwrap();
+        assert_eq!(
+            plan["subjects"],
+            json!([
+                { "subject": "Ma", "mode": "exact" },
+                { "subject": "C++", "mode": "exact" }
+            ])
+        );
+    }
+}
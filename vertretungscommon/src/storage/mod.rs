@@ -0,0 +1,72 @@
+mod postgres;
+mod sqlite;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::postgres::PgConnectOptions;
+use std::{error::Error, sync::Arc};
+use uuid::Uuid;
+
+use crate::config::DatabaseConfig;
+
+pub use postgres::PgStorage;
+pub use sqlite::SqliteStorage;
+
+#[derive(Debug, Clone)]
+pub struct User {
+    pub discord_id: i64,
+    pub active: bool,
+    pub embed: bool,
+    /// the stored plan, which may still be of an older version
+    pub data: Value,
+    pub token: Option<Uuid>,
+}
+
+/// Users and their plans, implemented for Postgres and an embedded SQLite.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn user(&self, discord_id: i64) -> Result<Option<User>, sqlx::Error>;
+
+    async fn user_by_token(&self, token: &Uuid) -> Result<Option<User>, sqlx::Error>;
+
+    async fn users(&self, only_active: bool) -> Result<Vec<User>, sqlx::Error>;
+
+    /// Stores the plan of a new user, returns `false` if the user already exists.
+    async fn insert_plan(&self, discord_id: i64, plan: &Value) -> Result<bool, sqlx::Error>;
+
+    /// Stores the plan, a new user starts active and without embeds.
+    async fn upsert_plan(&self, discord_id: i64, plan: &Value) -> Result<(), sqlx::Error>;
+
+    async fn delete_user(&self, discord_id: i64) -> Result<bool, sqlx::Error>;
+
+    async fn set_active(&self, discord_id: i64, active: bool) -> Result<bool, sqlx::Error>;
+
+    async fn set_embed(&self, discord_id: i64, embed: bool) -> Result<bool, sqlx::Error>;
+
+    /// Returns the secret token of the user and creates it on first use.
+    async fn ensure_token(&self, discord_id: i64) -> Result<Option<Uuid>, sqlx::Error>;
+}
+
+/// Connects to `database.url`, `sqlite:` urls use the embedded backend,
+/// without it the postgres given by the other settings is used.
+/// The migrations of the backend are run before it is returned.
+pub async fn connect(config: &DatabaseConfig) -> Result<Arc<dyn Storage>, Box<dyn Error>> {
+    let max = config.max_connections;
+    match &config.url {
+        Some(url) if url.starts_with("sqlite:") => {
+            Ok(Arc::new(SqliteStorage::connect(url, max).await?))
+        }
+        Some(url) => Ok(Arc::new(PgStorage::connect(url.parse()?, max).await?)),
+        None => Ok(Arc::new(
+            PgStorage::connect(
+                PgConnectOptions::new()
+                    .host(&config.host)
+                    .database(&config.name)
+                    .username(&config.user)
+                    .password(&config.password),
+                max,
+            )
+            .await?,
+        )),
+    }
+}
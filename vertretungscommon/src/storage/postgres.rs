@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::postgres::{PgConnectOptions, PgPool, PgPoolOptions, PgRow};
+use sqlx::types::Json;
+use sqlx::Row;
+use uuid::Uuid;
+
+use super::{Storage, User};
+
+const USER_COLUMNS: &str = "\"discord_id\", \"active\", \"embed\", \"data\", \"token\"";
+
+pub struct PgStorage(PgPool);
+
+impl PgStorage {
+    pub async fn connect(
+        options: PgConnectOptions,
+        max_connections: u32,
+    ) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!("../migrations/postgres").run(&pool).await?;
+        Ok(PgStorage(pool))
+    }
+}
+
+fn to_user(row: PgRow) -> Result<User, sqlx::Error> {
+    Ok(User {
+        discord_id: row.try_get(0)?,
+        active: row.try_get(1)?,
+        embed: row.try_get(2)?,
+        data: row.try_get(3)?,
+        token: row.try_get(4)?,
+    })
+}
+
+#[async_trait]
+impl Storage for PgStorage {
+    async fn user(&self, discord_id: i64) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"discord_id\" = $1"
+        ))
+        .bind(discord_id)
+        .fetch_optional(&self.0)
+        .await?
+        .map(to_user)
+        .transpose()
+    }
+
+    async fn user_by_token(&self, token: &Uuid) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"token\" = $1"
+        ))
+        .bind(token)
+        .fetch_optional(&self.0)
+        .await?
+        .map(to_user)
+        .transpose()
+    }
+
+    async fn users(&self, only_active: bool) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"active\" OR NOT $1"
+        ))
+        .bind(only_active)
+        .fetch_all(&self.0)
+        .await?
+        .into_iter()
+        .map(to_user)
+        .collect()
+    }
+
+    async fn insert_plan(&self, discord_id: i64, plan: &Value) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            "INSERT INTO \"user\" (\"discord_id\", \"active\", \"embed\", \"data\") VALUES ($1,$2,$3,$4)
+            ON CONFLICT (discord_id) DO NOTHING",
+        )
+        .bind(discord_id)
+        .bind(true)
+        .bind(false)
+        .bind(Json(plan))
+        .execute(&self.0)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn upsert_plan(&self, discord_id: i64, plan: &Value) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO \"user\" (\"discord_id\", \"active\", \"embed\", \"data\") VALUES ($1,$2,$3,$4)
+            ON CONFLICT (discord_id) DO UPDATE SET \"data\" = EXCLUDED.data",
+        )
+        .bind(discord_id)
+        .bind(true)
+        .bind(false)
+        .bind(Json(plan))
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_user(&self, discord_id: i64) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("DELETE FROM \"user\" WHERE \"discord_id\" = $1")
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn set_active(&self, discord_id: i64, active: bool) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("UPDATE \"user\" SET \"active\"=$1 WHERE \"discord_id\"=$2")
+            .bind(active)
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn set_embed(&self, discord_id: i64, embed: bool) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("UPDATE \"user\" SET \"embed\"=$1 WHERE \"discord_id\"=$2")
+            .bind(embed)
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn ensure_token(&self, discord_id: i64) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query(
+            "UPDATE \"user\" SET \"token\" = COALESCE(\"token\", $1)
+            WHERE \"discord_id\" = $2 RETURNING \"token\"",
+        )
+        .bind(Uuid::new_v4())
+        .bind(discord_id)
+        .fetch_optional(&self.0)
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()
+    }
+}
@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::types::Json;
+use sqlx::Row;
+use std::str::FromStr;
+use uuid::Uuid;
+
+use super::{Storage, User};
+
+const USER_COLUMNS: &str = "\"discord_id\", \"active\", \"embed\", \"data\", \"token\"";
+
+pub struct SqliteStorage(SqlitePool);
+
+impl SqliteStorage {
+    pub async fn connect(url: &str, max_connections: u32) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!("../migrations/sqlite").run(&pool).await?;
+        Ok(SqliteStorage(pool))
+    }
+}
+
+fn to_user(row: SqliteRow) -> Result<User, sqlx::Error> {
+    Ok(User {
+        discord_id: row.try_get(0)?,
+        active: row.try_get(1)?,
+        embed: row.try_get(2)?,
+        data: row.try_get::<Json<Value>, _>(3)?.0,
+        token: row.try_get(4)?,
+    })
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn user(&self, discord_id: i64) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"discord_id\" = ?1"
+        ))
+        .bind(discord_id)
+        .fetch_optional(&self.0)
+        .await?
+        .map(to_user)
+        .transpose()
+    }
+
+    async fn user_by_token(&self, token: &Uuid) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"token\" = ?1"
+        ))
+        .bind(token)
+        .fetch_optional(&self.0)
+        .await?
+        .map(to_user)
+        .transpose()
+    }
+
+    async fn users(&self, only_active: bool) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query(&format!(
+            "SELECT {USER_COLUMNS} FROM \"user\" WHERE \"active\" OR NOT ?1"
+        ))
+        .bind(only_active)
+        .fetch_all(&self.0)
+        .await?
+        .into_iter()
+        .map(to_user)
+        .collect()
+    }
+
+    async fn insert_plan(&self, discord_id: i64, plan: &Value) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query(
+            "INSERT INTO \"user\" (\"discord_id\", \"active\", \"embed\", \"data\") VALUES (?1,?2,?3,?4)
+            ON CONFLICT (discord_id) DO NOTHING",
+        )
+        .bind(discord_id)
+        .bind(true)
+        .bind(false)
+        .bind(Json(plan))
+        .execute(&self.0)
+        .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn upsert_plan(&self, discord_id: i64, plan: &Value) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO \"user\" (\"discord_id\", \"active\", \"embed\", \"data\") VALUES (?1,?2,?3,?4)
+            ON CONFLICT (discord_id) DO UPDATE SET \"data\" = EXCLUDED.data",
+        )
+        .bind(discord_id)
+        .bind(true)
+        .bind(false)
+        .bind(Json(plan))
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_user(&self, discord_id: i64) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("DELETE FROM \"user\" WHERE \"discord_id\" = ?1")
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn set_active(&self, discord_id: i64, active: bool) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("UPDATE \"user\" SET \"active\"=?1 WHERE \"discord_id\"=?2")
+            .bind(active)
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn set_embed(&self, discord_id: i64, embed: bool) -> Result<bool, sqlx::Error> {
+        let res = sqlx::query("UPDATE \"user\" SET \"embed\"=?1 WHERE \"discord_id\"=?2")
+            .bind(embed)
+            .bind(discord_id)
+            .execute(&self.0)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn ensure_token(&self, discord_id: i64) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query(
+            "UPDATE \"user\" SET \"token\" = COALESCE(\"token\", ?1)
+            WHERE \"discord_id\" = ?2 RETURNING \"token\"",
+        )
+        .bind(Uuid::new_v4())
+        .bind(discord_id)
+        .fetch_optional(&self.0)
+        .await?
+        .map(|row| row.try_get(0))
+        .transpose()
+    }
+}
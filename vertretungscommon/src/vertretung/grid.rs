@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// The periods `from` to `to`, both included, e.g. 1–2 for a double period.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PeriodRange {
+    pub from: i64,
+    pub to: i64,
+}
+
+impl PeriodRange {
+    pub fn single(period: i64) -> Self {
+        PeriodRange {
+            from: period,
+            to: period,
+        }
+    }
+
+    pub fn contains(&self, period: i64) -> bool {
+        (self.from..=self.to).contains(&period)
+    }
+
+    pub fn overlaps(&self, other: &PeriodRange) -> bool {
+        self.from <= other.to && other.from <= self.to
+    }
+}
+
+impl Display for PeriodRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.from == self.to {
+            true => write!(f, "{}.", self.from),
+            false => write!(f, "{}.–{}.", self.from, self.to),
+        }
+    }
+}
+
+/// The lesson blocks of a school day in order, a block lasts from one break
+/// to the next. Periods outside of all blocks, like a zero or an evening
+/// period, are single blocks of their own.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct PeriodGrid {
+    pub blocks: Vec<PeriodRange>,
+}
+
+impl Default for PeriodGrid {
+    /// The double periods 1./2. to 9./10. of the BSZ.
+    fn default() -> Self {
+        PeriodGrid {
+            blocks: (0..5)
+                .map(|n| PeriodRange {
+                    from: n * 2 + 1,
+                    to: n * 2 + 2,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PeriodGrid {
+    pub fn block_of(&self, period: i64) -> PeriodRange {
+        self.blocks
+            .iter()
+            .find(|block| block.contains(period))
+            .copied()
+            .unwrap_or(PeriodRange::single(period))
+    }
+
+    /// Whether a lesson in `periods` stays within one block, lessons
+    /// never go on over a break.
+    pub fn fits(&self, periods: &PeriodRange) -> bool {
+        periods.from <= periods.to && self.block_of(periods.from).contains(periods.to)
+    }
+
+    pub fn has_period(&self, period: i64) -> bool {
+        self.blocks.iter().any(|block| block.contains(period))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.blocks.is_empty() {
+            return Err("needs at least one block".to_string());
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            if block.from < 0 || block.from > block.to {
+                return Err(format!(
+                    "block {block} has to go from a lower to a higher period"
+                ));
+            }
+            if let Some(previous) = i.checked_sub(1).map(|i| &self.blocks[i]) {
+                if previous.to >= block.from {
+                    return Err(format!("block {block} has to come after {previous}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+
+/// What a substitution means, derived from its Art column and message.
+/// The raw texts stay in `Lesson.vtype` and `Lesson.message`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SubstitutionKind {
+    Cancellation,
+    /// another teacher takes the lesson
+    Substitute,
+    RoomChange,
+    /// the lesson takes place at another time
+    Moved,
+    SelfStudy,
+    /// excursions, school events and the like replace the lesson
+    Event,
+    Exam,
+    Other,
+}
+
+/// Keywords by kind, checked in this order against the lowercase text, so
+/// `Raum-Vertretung` is a room change and `Klausur entfällt` a cancellation.
+const KEYWORDS: [(SubstitutionKind, &[&str]); 7] = [
+    (
+        SubstitutionKind::Cancellation,
+        &["ausfall", "entfall", "entfällt", "fällt aus", "frei"],
+    ),
+    (
+        SubstitutionKind::Exam,
+        &["klausur", "prüfung", "klassenarbeit", "test"],
+    ),
+    (
+        SubstitutionKind::Event,
+        &[
+            "veranstaltung",
+            "exkursion",
+            "ausflug",
+            "projekt",
+            "sportfest",
+        ],
+    ),
+    (
+        SubstitutionKind::SelfStudy,
+        &[
+            "eva",
+            "selbststudium",
+            "selbstlernen",
+            "stillarbeit",
+            "aufgaben",
+        ],
+    ),
+    (
+        SubstitutionKind::Moved,
+        &["verlegung", "verlegt", "verschoben", "tausch", "vorgezogen"],
+    ),
+    (
+        SubstitutionKind::RoomChange,
+        &["raum", "raumänderung", "raumwechsel"],
+    ),
+    (
+        SubstitutionKind::Substitute,
+        &["vertretung", "betreuung", "mitbetreuung"],
+    ),
+];
+
+impl SubstitutionKind {
+    /// The kind named by the Art column, or else by the message. A
+    /// cancellation in either one wins, the Art `Vertretung` with the
+    /// message `entfällt` is still a cancellation. `None` if neither
+    /// contains a known keyword.
+    pub fn from_texts(vtype: &str, message: &str) -> Option<SubstitutionKind> {
+        let texts = [vtype.to_lowercase(), message.to_lowercase()];
+        let (cancellation, others) = KEYWORDS.split_first()?;
+        if texts.iter().any(|text| contains_any(text, cancellation.1)) {
+            return Some(cancellation.0);
+        }
+        texts.iter().find_map(|text| {
+            others
+                .iter()
+                .find_map(|(kind, keywords)| contains_any(text, keywords).then_some(*kind))
+        })
+    }
+}
+
+/// Whether the lowercase `text` contains one of `keywords`.
+fn contains_any(text: &str, keywords: &[&str]) -> bool {
+    let words: Vec<&str> = text.split(|c: char| !c.is_alphanumeric()).collect();
+    keywords.iter().any(|keyword| match keyword.contains(' ') {
+        true => text.contains(keyword),
+        // short ones only as whole words, "eva" is no "evaluation"
+        false if keyword.chars().count() <= 4 => words.contains(keyword),
+        false => words.iter().any(|word| word.starts_with(keyword)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubstitutionKind::{self, *};
+
+    fn kind(vtype: &str, message: &str) -> Option<SubstitutionKind> {
+        SubstitutionKind::from_texts(vtype, message)
+    }
+
+    #[test]
+    fn cancellation_in_either_text_wins() {
+        assert_eq!(kind("Vertretung", "entfällt"), Some(Cancellation));
+        assert_eq!(kind("Raum-Vertretung", "fällt aus"), Some(Cancellation));
+        assert_eq!(kind("Entfall", "Vertretung"), Some(Cancellation));
+        assert_eq!(kind("Klausur", "entfällt"), Some(Cancellation));
+    }
+
+    #[test]
+    fn art_comes_before_the_message() {
+        assert_eq!(kind("Vertretung", "Klausur"), Some(Substitute));
+        assert_eq!(kind("", "Klausur"), Some(Exam));
+        assert_eq!(kind("Sondereinsatz", "verlegt von Mo 3. Std"), Some(Moved));
+    }
+
+    #[test]
+    fn keywords_are_checked_in_order() {
+        assert_eq!(kind("Raum-Vertretung", ""), Some(RoomChange));
+        assert_eq!(kind("Klausur im Projekt", ""), Some(Exam));
+    }
+
+    #[test]
+    fn short_keywords_only_as_whole_words() {
+        assert_eq!(kind("EVA", ""), Some(SelfStudy));
+        assert_eq!(kind("Evaluation", ""), None);
+        assert_eq!(kind("", ""), None);
+    }
+}
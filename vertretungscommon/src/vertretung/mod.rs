@@ -0,0 +1,6 @@
+pub mod grid;
+pub mod kinds;
+pub mod moves;
+pub mod notes;
+pub mod revocations;
+pub mod vertretungsdings;
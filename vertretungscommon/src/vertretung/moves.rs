@@ -0,0 +1,177 @@
+use chrono::{Datelike, NaiveDate};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+use super::grid::PeriodRange;
+use super::vertretungsdings::WEEKDAY_NAMES;
+
+/// The other end in a message, like `verlegt von Mo 3. Std`, `statt Di
+/// 5./6.` or `verschoben auf Mi 21.10. 1.-2.`.
+static MOVE_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?ix)
+        \b(?P<verb>verlegt|verschoben|vorgezogen|nachgeholt|getauscht|tausch|anstatt|statt)
+        (?:\s+(?P<prep>von|vom|auf|nach|zu|mit))?
+        \s+(?P<day>montag|dienstag|mittwoch|donnerstag|freitag|mo|di|mi|do|fr)\b\.?,?
+        \s*(?:(?P<dd>\d{1,2})\.(?P<mm>\d{1,2})\.(?P<yyyy>\d{4})?,?\s*)?
+        (?P<from>\d{1,2})\.?(?:\s*[-–/]\s*(?P<to>\d{1,2})\.?)?",
+    )
+    .expect("move pattern is valid")
+});
+
+/// How a lesson relates to the other end of its move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveKind {
+    /// moved here from the other end, `verlegt von`
+    From,
+    /// moved from here to the other end, `verlegt auf`
+    To,
+    /// takes place instead of the lesson at the other end, `statt`
+    Instead,
+    /// swapped with the lesson at the other end, `Tausch mit`
+    Swap,
+}
+
+impl MoveKind {
+    /// The kind seen from the other end.
+    pub fn reverse(self) -> MoveKind {
+        match self {
+            // the lesson a `statt` takes the place of was moved to this end
+            MoveKind::From | MoveKind::Instead => MoveKind::To,
+            MoveKind::To => MoveKind::From,
+            MoveKind::Swap => MoveKind::Swap,
+        }
+    }
+}
+
+/// The other end of a moved lesson, named in its message like
+/// `verlegt von Mo 3. Std` or `statt Di 5./6.`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub kind: MoveKind,
+    /// weekday of the other end, e.g. `Montag`
+    pub day: String,
+    /// given in the message or known once the other day is published
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<NaiveDate>,
+    pub periods: PeriodRange,
+}
+
+impl Move {
+    /// Finds a move in the message of a lesson on `date`, which a date
+    /// without a year in the message is resolved against.
+    pub fn parse(message: &str, date: NaiveDate) -> Option<Move> {
+        if message.trim().is_empty() {
+            return None;
+        }
+        let caps = MOVE_PATTERN.captures(message)?;
+
+        let verb = caps["verb"].to_lowercase();
+        let prep = caps.name("prep").map(|m| m.as_str().to_lowercase());
+        let kind = match (verb.as_str(), prep.as_deref()) {
+            ("statt" | "anstatt", _) => MoveKind::Instead,
+            ("tausch" | "getauscht", _) | (_, Some("mit")) => MoveKind::Swap,
+            (_, Some("von" | "vom")) => MoveKind::From,
+            _ => MoveKind::To,
+        };
+
+        let day_abbr = caps["day"].to_lowercase();
+        let day = WEEKDAY_NAMES
+            .iter()
+            .find(|name| name.to_lowercase().starts_with(&day_abbr))?
+            .to_string();
+
+        let from: i64 = caps["from"].parse().ok()?;
+        let to: i64 = match caps.name("to") {
+            Some(to) => to.as_str().parse().ok()?,
+            None => from,
+        };
+
+        let other_date = match (caps.name("dd"), caps.name("mm")) {
+            (Some(dd), Some(mm)) => {
+                let (dd, mm) = (dd.as_str().parse().ok()?, mm.as_str().parse().ok()?);
+                match caps.name("yyyy") {
+                    Some(yyyy) => NaiveDate::from_ymd_opt(yyyy.as_str().parse().ok()?, mm, dd),
+                    // the closest one, moves over new year go into another year
+                    None => [date.year() - 1, date.year(), date.year() + 1]
+                        .into_iter()
+                        .filter_map(|year| NaiveDate::from_ymd_opt(year, mm, dd))
+                        .min_by_key(|other| (*other - date).num_days().abs()),
+                }
+            }
+            _ => None,
+        };
+
+        Some(Move {
+            kind,
+            day,
+            date: other_date,
+            periods: PeriodRange {
+                from: from.min(to),
+                to: from.max(to),
+            },
+        })
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let verb = match self.kind {
+            MoveKind::From => "verlegt von",
+            MoveKind::To => "verlegt auf",
+            MoveKind::Instead => "statt",
+            MoveKind::Swap => "getauscht mit",
+        };
+        write!(f, "{verb} {}", self.day)?;
+        if let Some(date) = self.date {
+            write!(f, " {}", date.format("%d.%m."))?;
+        }
+        write!(f, " {}", self.periods)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn moved_from_a_single_period() {
+        let moved = Move::parse("verlegt von Mo 3. Std", monday()).unwrap();
+        assert_eq!(moved.kind, MoveKind::From);
+        assert_eq!(moved.day, "Montag");
+        assert_eq!(moved.date, None);
+        assert_eq!(moved.periods, PeriodRange::single(3));
+        assert_eq!(moved.to_string(), "verlegt von Montag 3.");
+    }
+
+    #[test]
+    fn instead_of_a_double_period() {
+        let moved = Move::parse("statt Di 5./6.", monday()).unwrap();
+        assert_eq!(moved.kind, MoveKind::Instead);
+        assert_eq!(moved.day, "Dienstag");
+        assert_eq!(moved.periods, PeriodRange { from: 5, to: 6 });
+        // the Tuesday lesson went to Monday
+        assert_eq!(moved.kind.reverse(), MoveKind::To);
+    }
+
+    #[test]
+    fn date_without_a_year_is_the_closest_one() {
+        let moved = Move::parse("verschoben auf Fr 02.01. 1.-2.", monday()).unwrap();
+        assert_eq!(moved.kind, MoveKind::To);
+        assert_eq!(moved.date, NaiveDate::from_ymd_opt(2027, 1, 2));
+        assert_eq!(moved.periods, PeriodRange { from: 1, to: 2 });
+    }
+
+    #[test]
+    fn messages_without_a_move() {
+        assert_eq!(Move::parse("", monday()), None);
+        assert_eq!(Move::parse("Aufgaben im Lernraum", monday()), None);
+    }
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// What a substitution page says besides its lessons, the "Nachrichten zum
+/// Tag" like shortened days and the lists of absent teachers and classes
+/// and of blocked rooms.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayNotes {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub absent_teachers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub absent_classes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_rooms: Vec<String>,
+}
+
+impl DayNotes {
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+            && self.absent_teachers.is_empty()
+            && self.absent_classes.is_empty()
+            && self.blocked_rooms.is_empty()
+    }
+}
@@ -0,0 +1,32 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::vertretungsdings::{Lesson, Plan, VDay};
+
+/// A substitution or a whole day which was published and then taken back,
+/// e.g. an `Entfall` which no longer applies.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Revocation {
+    /// increasing, clients ask for those after the last one they know
+    pub id: u64,
+    pub day: String,
+    pub date: NaiveDate,
+    /// the substitution taken back, `None` if the whole day was withdrawn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lesson: Option<Lesson>,
+}
+
+impl Revocation {
+    /// Whether the user of `plan` was told about it, a withdrawn day only
+    /// concerns those with substitutions in its `previous` version.
+    pub fn concerns(&self, plan: &Plan, previous: &[VDay]) -> bool {
+        match &self.lesson {
+            Some(lesson) => plan.concerns(lesson),
+            None => previous
+                .iter()
+                .filter(|vday| vday.day() == self.day)
+                .flat_map(VDay::lessons)
+                .any(|lesson| plan.concerns(lesson)),
+        }
+    }
+}
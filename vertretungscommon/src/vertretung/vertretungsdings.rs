@@ -0,0 +1,592 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::grid::{PeriodGrid, PeriodRange};
+use super::kinds::SubstitutionKind;
+use super::moves::{Move, MoveKind};
+use super::notes::DayNotes;
+use crate::classes::ClassSet;
+use crate::courses::CourseSelector;
+
+pub const WEEKDAY_NAMES: [&str; 5] = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag"];
+
+/// The week of the two week cycle of the Blockplan, `A` lessons are held in
+/// `I` weeks and `B` lessons in `II` weeks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Zyklus {
+    #[default]
+    I,
+    II,
+}
+
+/// The published day merged into the regular lessons of `plan`, `None` if
+/// the plan has no lessons on its weekday.
+pub fn get_day(
+    VDay(day_str, zyklus, v_lessons, notes): &VDay,
+    plan: &Plan,
+    grid: &PeriodGrid,
+) -> Option<Day> {
+    let day_name = day_str.split_whitespace().next()?;
+    let plan_day = plan.day(day_name)?;
+
+    let mut res_day: Day = Day::new(day_str);
+    res_day.notes = notes.clone();
+    res_day.add_regular(plan_day, zyklus);
+    res_day.add_substitutions(v_lessons, plan, grid);
+    res_day.resolve(plan);
+
+    Some(res_day)
+}
+
+/// Words of a text, compared case-insensitively.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanLesson {
+    pub subject: String,
+    pub room: String,
+    pub teacher: String,
+    /// the class taught, only used by teacher plans
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub class: String,
+}
+
+impl PlanLesson {
+    /// The lesson starting in period `time`.
+    pub fn to_lesson(&self, time: i64) -> Lesson {
+        Lesson {
+            class: self.class.clone(),
+            ..Lesson::new(
+                time,
+                self.subject.as_str(),
+                self.room.as_str(),
+                self.teacher.as_str(),
+            )
+        }
+    }
+}
+
+/// How a teacher is part of a substitution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeacherRole {
+    /// teaches the lesson instead
+    Substitute,
+    /// is struck out, named in the message or the lesson is cancelled
+    Absent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lesson {
+    pub class: String,
+    pub time: i64,
+    /// the current values, without the struck out ones
+    pub subject: String,
+    pub room: String,
+    pub teacher: String,
+    /// the original values struck out on the page
+    #[serde(default, skip_serializing_if = "Struck::is_empty")]
+    pub struck: Struck,
+    pub vtype: String,
+    pub message: String,
+    /// meaning of `vtype` and `message`, `None` for regular lessons
+    #[serde(default)]
+    pub kind: Option<SubstitutionKind>,
+    /// differences to the regular lesson, set when merged into a `Day`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<Change>,
+    /// the other end if the message names where the lesson was moved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved: Option<Move>,
+}
+
+/// The values a substitution strikes out, e.g. the absent teacher next to
+/// the substitute.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Struck {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub subject: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub room: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub teacher: String,
+}
+
+impl Struck {
+    pub fn is_empty(&self) -> bool {
+        self.subject.is_empty() && self.room.is_empty() && self.teacher.is_empty()
+    }
+}
+
+/// What a substitution changes about the regular lesson.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Change {
+    Room {
+        from: String,
+        to: String,
+    },
+    Teacher {
+        from: String,
+        to: String,
+    },
+    Cancelled,
+    /// there is no regular lesson it takes the place of
+    Added,
+    Moved(Move),
+}
+
+impl Lesson {
+    fn new(time: i64, subject: &str, room: &str, teacher: &str) -> Lesson {
+        Lesson {
+            class: String::new(),
+            time,
+            subject: subject.to_string(),
+            room: room.to_string(),
+            teacher: teacher.to_string(),
+            struck: Struck::default(),
+            vtype: String::new(),
+            message: String::new(),
+            kind: None,
+            changes: Vec::new(),
+            moved: None,
+        }
+    }
+
+    /// The teacher cell shows the absent teacher struck out next to the
+    /// substitute, e.g. `<s>MÜL</s> SCH`.
+    pub fn teacher_role(&self, teacher: &str) -> Option<TeacherRole> {
+        let teacher = teacher.trim().to_lowercase();
+
+        if words(&self.struck.teacher).contains(&teacher) {
+            return Some(TeacherRole::Absent);
+        }
+        if words(&self.teacher).contains(&teacher) {
+            return match self.is_cancelled() || self.is_moved_away() {
+                true => Some(TeacherRole::Absent),
+                false => Some(TeacherRole::Substitute),
+            };
+        }
+        match words(&self.message).contains(&teacher) {
+            true => Some(TeacherRole::Absent),
+            false => None,
+        }
+    }
+
+    /// The teachers of the teacher cell, struck out or not, lowercase.
+    pub fn teachers(&self) -> Vec<String> {
+        [words(&self.struck.teacher), words(&self.teacher)].concat()
+    }
+
+    /// Rooms freed and taken by the substitution. A room change shows the
+    /// original room struck out, a cancelled lesson frees all its rooms.
+    pub fn room_changes(&self) -> (Vec<String>, Vec<String>) {
+        let rooms = |text: &str| -> Vec<String> {
+            text.split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|room| !room.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        match self.is_cancelled() || self.is_moved_away() {
+            true => (
+                [rooms(&self.struck.room), rooms(&self.room)].concat(),
+                Vec::new(),
+            ),
+            false => (rooms(&self.struck.room), rooms(&self.room)),
+        }
+    }
+
+    /// The kind named by the Art column or message, otherwise a struck out
+    /// teacher means a substitute and a struck out room a room change.
+    pub fn classify(&self) -> SubstitutionKind {
+        SubstitutionKind::from_texts(&self.vtype, &self.message).unwrap_or_else(|| {
+            if self.moved.is_some() {
+                SubstitutionKind::Moved
+            } else if !self.struck.teacher.is_empty() {
+                SubstitutionKind::Substitute
+            } else if !self.struck.room.is_empty() {
+                SubstitutionKind::RoomChange
+            } else {
+                SubstitutionKind::Other
+            }
+        })
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.kind == Some(SubstitutionKind::Cancellation)
+    }
+
+    /// Whether the lesson was moved to another time and is not held here.
+    pub fn is_moved_away(&self) -> bool {
+        self.moved.as_ref().map(|moved| moved.kind) == Some(MoveKind::To)
+    }
+
+    /// Differences to `planned`, the regular lesson this substitution takes
+    /// the place of.
+    fn changes_to(&self, planned: Option<&Lesson>) -> Vec<Change> {
+        let mut changes: Vec<Change> = self.moved.iter().cloned().map(Change::Moved).collect();
+        if self.is_moved_away() {
+            return changes;
+        }
+        if self.is_cancelled() {
+            changes.push(Change::Cancelled);
+            return changes;
+        }
+        let planned = match planned {
+            Some(planned) => planned,
+            None => {
+                changes.push(Change::Added);
+                return changes;
+            }
+        };
+        let (room, teacher) = (self.room.as_str(), self.teacher.as_str());
+        let (planned_room, planned_teacher) = (planned.room.trim(), planned.teacher.trim());
+        if !room.is_empty() && room.to_lowercase() != planned_room.to_lowercase() {
+            changes.push(Change::Room {
+                from: planned_room.to_string(),
+                to: room.to_string(),
+            });
+        }
+        if !teacher.is_empty() && teacher.to_lowercase() != planned_teacher.to_lowercase() {
+            changes.push(Change::Teacher {
+                from: planned_teacher.to_string(),
+                to: teacher.to_string(),
+            });
+        }
+        changes
+    }
+
+    /// Whether both are about the same lesson, the same class, period and
+    /// regular subject, the struck out one if it was changed.
+    pub fn same_slot(&self, other: &Lesson) -> bool {
+        self.class == other.class
+            && self.time == other.time
+            && self.regular_subject() == other.regular_subject()
+    }
+
+    fn regular_subject(&self) -> &str {
+        match self.struck.subject.is_empty() {
+            true => &self.subject,
+            false => &self.struck.subject,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanKind {
+    /// substitutions of one class, filtered by the subjects
+    #[default]
+    Class,
+    /// substitutions of one teacher across all classes
+    Teacher,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Plan {
+    pub version: u64,
+    /// id of the configured source the substitutions are taken from
+    pub source: String,
+    #[serde(default)]
+    pub kind: PlanKind,
+    /// exact class, or a prefix ending with `*` like `TIG2*`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub class_name: String,
+    /// group of the class, e.g. `1` for `TIG21/1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// abbreviation of the teacher of a teacher plan
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub teacher: String,
+    pub days: Vec<PlanDay>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subjects: Vec<CourseSelector>,
+}
+
+impl Plan {
+    /// Whether a substitution belongs to this plan.
+    pub fn concerns(&self, lesson: &Lesson) -> bool {
+        match self.kind {
+            PlanKind::Class => {
+                let subjects = [lesson.subject.as_str(), lesson.struck.subject.as_str()];
+                ClassSet::parse(&lesson.class).matches(&self.class_name, self.group.as_deref())
+                    && self.subjects.iter().any(|course| {
+                        course.matches(&subjects, |teacher| lesson.teacher_role(teacher).is_some())
+                    })
+            }
+            PlanKind::Teacher => lesson.teacher_role(&self.teacher).is_some(),
+        }
+    }
+
+    /// Whether a substitution takes the place of the regular lesson
+    /// `planned`. In a class plan those in its first period do. A teacher
+    /// can substitute in another class while keeping the own lesson, so
+    /// only substitutions of its class or ones the teacher is absent from
+    /// count.
+    pub fn replaces(&self, sub: &Lesson, planned: &Lesson) -> bool {
+        match self.kind {
+            PlanKind::Class => sub.time == planned.time,
+            PlanKind::Teacher => match planned.class.is_empty() {
+                true => sub.teacher_role(&self.teacher) == Some(TeacherRole::Absent),
+                false => ClassSet::parse(&sub.class).matches(&planned.class, None),
+            },
+        }
+    }
+
+    /// Whether a substitution in the periods of the regular lesson `planned`
+    /// changes it. In a class plan all of them do, also those in a later
+    /// period which don't replace it, in a teacher plan those which
+    /// replace it.
+    pub fn changes(&self, sub: &Lesson, planned: &Lesson) -> bool {
+        match self.kind {
+            PlanKind::Class => true,
+            PlanKind::Teacher => self.replaces(sub, planned),
+        }
+    }
+
+    pub fn day(&self, day_name: &str) -> Option<&PlanDay> {
+        self.days.iter().find(|item| item.day.contains(day_name))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub enum WeekOption {
+    #[default]
+    None,
+    AandB(PlanLesson),
+    A(PlanLesson),
+    B(PlanLesson),
+    AorB(PlanLesson, PlanLesson),
+}
+
+impl WeekOption {
+    /// The lesson taking place in a week of the zyklus.
+    pub fn lesson(&self, zyklus: &Zyklus) -> Option<&PlanLesson> {
+        match (self, zyklus) {
+            (WeekOption::AandB(l), _) => Some(l),
+            (WeekOption::A(l), Zyklus::I) | (WeekOption::B(l), Zyklus::II) => Some(l),
+            (WeekOption::AorB(l, _), Zyklus::I) | (WeekOption::AorB(_, l), Zyklus::II) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+/// A lesson of the plan and the periods it takes, which stay within one
+/// block of the grid.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlanBlock {
+    pub periods: PeriodRange,
+    pub lesson: WeekOption,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PlanDay {
+    pub day: String,
+    /// the periods with lessons, free ones are left out
+    pub lessons: Vec<PlanBlock>,
+}
+
+/// A published substitution page, its day like `Montag 19.10.2026`, the
+/// zyklus of its week, the substitutions and the notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VDay(
+    pub String,
+    pub Zyklus,
+    pub Vec<Lesson>,
+    #[serde(default)] pub DayNotes,
+);
+
+impl VDay {
+    pub fn day(&self) -> &str {
+        &self.0
+    }
+
+    pub fn date(&self) -> Option<NaiveDate> {
+        parse_day_date(&self.0)
+    }
+
+    pub fn lessons(&self) -> &Vec<Lesson> {
+        &self.2
+    }
+
+    /// The same day with only the lessons `keep` returns true for.
+    pub fn filtered(&self, keep: impl Fn(&Lesson) -> bool) -> VDay {
+        VDay(
+            self.0.clone(),
+            self.1,
+            self.2
+                .iter()
+                .filter(|lesson| keep(lesson))
+                .cloned()
+                .collect(),
+            self.3.clone(),
+        )
+    }
+
+    pub fn notes(&self) -> &DayNotes {
+        &self.3
+    }
+}
+
+fn parse_day_date(day_str: &str) -> Option<NaiveDate> {
+    let date_str = day_str.split_whitespace().last()?;
+    NaiveDate::parse_from_str(date_str, "%d.%m.%Y").ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Day {
+    pub day: String,
+    #[serde(default)]
+    pub holiday: bool,
+    /// the notes of the substitution page
+    #[serde(default, skip_serializing_if = "DayNotes::is_empty")]
+    pub notes: DayNotes,
+    /// the blocks with lessons, in order of their periods
+    pub slots: Vec<Slot>,
+    /// substitutions of slots which are gone since an earlier version
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<Lesson>,
+}
+
+/// The periods of a planned lesson, or of a grid block with substitutions,
+/// with the regular lesson next to the substitutions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Slot {
+    pub periods: PeriodRange,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub planned: Option<Lesson>,
+    /// whether the substitutions take the place of `planned`
+    #[serde(default)]
+    pub replaced: bool,
+    #[serde(default)]
+    pub substitutions: Vec<Lesson>,
+}
+
+impl Slot {
+    /// The lessons taking place in the periods, by their first period.
+    pub fn lessons(&self) -> Vec<&Lesson> {
+        let planned = self.planned.iter().filter(|_| !self.replaced);
+        let mut lessons: Vec<&Lesson> = planned.chain(&self.substitutions).collect();
+        lessons.sort_by_key(|lesson| lesson.time);
+        lessons
+    }
+}
+
+impl Day {
+    pub fn new(day: &str) -> Day {
+        Day {
+            day: day.to_string(),
+            holiday: false,
+            notes: DayNotes::default(),
+            slots: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn date(&self) -> Option<NaiveDate> {
+        parse_day_date(&self.day)
+    }
+
+    /// The slot of exactly `periods`, added in order if it's missing.
+    fn slot(&mut self, periods: PeriodRange) -> &mut Slot {
+        let i = self.slots.partition_point(|slot| slot.periods < periods);
+        if self.slots.get(i).is_none_or(|slot| slot.periods != periods) {
+            let slot = Slot {
+                periods,
+                planned: None,
+                replaced: false,
+                substitutions: Vec::new(),
+            };
+            self.slots.insert(i, slot);
+        }
+        &mut self.slots[i]
+    }
+
+    /// The lessons of `plan_day` held in a week of `zyklus`.
+    pub fn add_regular(&mut self, plan_day: &PlanDay, zyklus: &Zyklus) {
+        for block in &plan_day.lessons {
+            if let Some(l) = block.lesson.lesson(zyklus) {
+                self.slot(block.periods).planned = Some(l.to_lesson(block.periods.from));
+            }
+        }
+    }
+
+    /// A substitution goes into the slot of its period, or else into one of
+    /// its grid block, unless a planned lesson already takes a part of it.
+    pub fn add_substitutions(&mut self, v_lessons: &[Lesson], plan: &Plan, grid: &PeriodGrid) {
+        for v_lesson in v_lessons.iter().filter(|item| plan.concerns(item)) {
+            let periods = match self
+                .slots
+                .iter()
+                .find(|s| s.periods.contains(v_lesson.time))
+            {
+                Some(slot) => slot.periods,
+                None => {
+                    let block = grid.block_of(v_lesson.time);
+                    match self.slots.iter().any(|slot| slot.periods.overlaps(&block)) {
+                        true => PeriodRange::single(v_lesson.time),
+                        false => block,
+                    }
+                }
+            };
+            self.slot(periods).substitutions.push(v_lesson.clone());
+        }
+    }
+
+    /// Decides which regular lessons are replaced, by the substitutions
+    /// which `Plan::replaces` them, and what the substitutions which
+    /// `Plan::changes` a lesson change about it.
+    pub fn resolve(&mut self, plan: &Plan) {
+        for slot in self.slots.iter_mut() {
+            if let Some(planned) = &slot.planned {
+                slot.replaced = slot
+                    .substitutions
+                    .iter()
+                    .any(|sub| plan.replaces(sub, planned));
+            }
+            let planned = slot.planned.clone();
+            for sub in slot.substitutions.iter_mut() {
+                let planned = planned
+                    .as_ref()
+                    .filter(|planned| plan.changes(sub, planned));
+                sub.changes = sub.changes_to(planned);
+            }
+        }
+    }
+
+    /// The same day with only what differs from `before`, the slots which
+    /// changed, the substitutions of the slots which are gone and the notes
+    /// if they did. `None` if nothing changed.
+    pub fn changes_since(&self, before: &Day) -> Option<Day> {
+        let slots: Vec<Slot> = self
+            .slots
+            .iter()
+            .filter(|slot| !before.slots.contains(slot))
+            .cloned()
+            .collect();
+        let removed: Vec<Lesson> = before
+            .slots
+            .iter()
+            .filter(|slot| !self.slots.iter().any(|now| now.periods == slot.periods))
+            .flat_map(|slot| slot.substitutions.iter().cloned())
+            .collect();
+        let notes = match self.notes == before.notes {
+            true => DayNotes::default(),
+            false => self.notes.clone(),
+        };
+        match slots.is_empty() && removed.is_empty() && notes.is_empty() {
+            true => None,
+            false => Some(Day {
+                day: self.day.clone(),
+                holiday: self.holiday,
+                notes,
+                slots,
+                removed,
+            }),
+        }
+    }
+}